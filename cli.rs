@@ -0,0 +1,257 @@
+//! Offline helpers for operating the bridge: hashing addresses, converting
+//! amounts and encoding contract calls.
+//!
+//! ```text
+//! bridge-cli hash-address <ADDRESS>
+//! bridge-cli hash-address --account <ACCOUNT_HEX>
+//! bridge-cli u256 to-hex <DECIMAL>
+//! bridge-cli u256 to-dec <HEX>
+//! bridge-cli encode-call <MESSAGE> [ARGS...]
+//! ```
+//!
+//! Amounts accept decimal or `0x`-prefixed hex. `encode-call` prints the
+//! message selector followed by its SCALE-encoded arguments, ready to be
+//! used as call data.
+
+use std::process::ExitCode;
+
+use bridge_cherry_contract::helpers::utils::{MultiChainAddrHash, U256};
+use scale::Encode;
+
+#[derive(Clone, Copy)]
+enum ArgKind {
+    Address,
+    Amount,
+    Nonce,
+    Flag,
+}
+
+const MESSAGES: &[(&str, &[ArgKind])] = &[
+    (
+        "bridge_out",
+        &[ArgKind::Address, ArgKind::Amount, ArgKind::Amount],
+    ),
+    (
+        "bridge_in",
+        &[
+            ArgKind::Address,
+            ArgKind::Amount,
+            ArgKind::Address,
+            ArgKind::Amount,
+            ArgKind::Nonce,
+        ],
+    ),
+    (
+        "bridge_cherry",
+        &[ArgKind::Amount, ArgKind::Amount, ArgKind::Address],
+    ),
+    (
+        "receive_cherry",
+        &[
+            ArgKind::Amount,
+            ArgKind::Address,
+            ArgKind::Amount,
+            ArgKind::Nonce,
+        ],
+    ),
+    (
+        "transfer",
+        &[ArgKind::Address, ArgKind::Address, ArgKind::Amount],
+    ),
+    (
+        "transfer_from",
+        &[
+            ArgKind::Address,
+            ArgKind::Address,
+            ArgKind::Address,
+            ArgKind::Amount,
+        ],
+    ),
+    (
+        "approve",
+        &[ArgKind::Address, ArgKind::Address, ArgKind::Amount],
+    ),
+    ("refund", &[ArgKind::Nonce]),
+    ("confirm_delivered", &[ArgKind::Nonce]),
+    ("mark_failed", &[ArgKind::Nonce]),
+    ("set_paused", &[ArgKind::Flag]),
+];
+
+const USAGE: &str = "usage:
+  bridge-cli hash-address <ADDRESS>
+  bridge-cli hash-address --account <ACCOUNT_HEX>
+  bridge-cli u256 to-hex <DECIMAL>
+  bridge-cli u256 to-dec <HEX>
+  bridge-cli encode-call <MESSAGE> [ARGS...]";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    match run(&args) {
+        Ok(output) => {
+            println!("{}", output);
+
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("bridge-cli: {}", e);
+
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: &[&str]) -> Result<String, String> {
+    match args {
+        ["hash-address", "--account", account] => {
+            let bytes = parse_account(account)?;
+            let hash: MultiChainAddrHash = bytes.as_slice().into();
+
+            Ok(format!("0x{}", hash.to_string_digest()))
+        }
+        ["hash-address", address] => Ok(format!(
+            "0x{}",
+            MultiChainAddrHash::from(address.to_string()).to_string_digest()
+        )),
+        ["u256", "to-hex", value] => Ok(format!("0x{}", parse_amount(value)?.to_hex())),
+        ["u256", "to-dec", value] => {
+            let value = if value.starts_with("0x") {
+                value.to_string()
+            } else {
+                format!("0x{}", value)
+            };
+
+            Ok(parse_amount(&value)?.to_decimal())
+        }
+        ["encode-call", message, call_args @ ..] => {
+            encode_call(message, call_args).map(|data| format!("0x{}", hex::encode(data)))
+        }
+        _ => Err(USAGE.to_string()),
+    }
+}
+
+/// The ink! selector of `message`: the first four bytes of the BLAKE2b-256
+/// hash of its name.
+fn selector(message: &str) -> [u8; 4] {
+    let hash = MultiChainAddrHash::make_hash(message.as_bytes());
+
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+fn encode_call(message: &str, args: &[&str]) -> Result<Vec<u8>, String> {
+    let (_, kinds) = MESSAGES
+        .iter()
+        .find(|(name, _)| *name == message)
+        .ok_or_else(|| {
+            let known: Vec<&str> = MESSAGES.iter().map(|(name, _)| *name).collect();
+
+            format!(
+                "unknown message {}, expected one of: {}",
+                message,
+                known.join(", ")
+            )
+        })?;
+
+    if args.len() != kinds.len() {
+        return Err(format!(
+            "{} takes {} arguments, got {}",
+            message,
+            kinds.len(),
+            args.len()
+        ));
+    }
+
+    let mut data = selector(message).to_vec();
+
+    for (kind, arg) in kinds.iter().zip(args) {
+        match kind {
+            ArgKind::Address => MultiChainAddrHash::from(arg.to_string()).encode_to(&mut data),
+            ArgKind::Amount => parse_amount(arg)?.encode_to(&mut data),
+            ArgKind::Nonce => arg
+                .parse::<u64>()
+                .map_err(|_| format!("invalid nonce {}", arg))?
+                .encode_to(&mut data),
+            ArgKind::Flag => arg
+                .parse::<bool>()
+                .map_err(|_| format!("invalid flag {}, expected true or false", arg))?
+                .encode_to(&mut data),
+        }
+    }
+
+    Ok(data)
+}
+
+fn parse_amount(value: &str) -> Result<U256, String> {
+    U256::parse(value).ok_or_else(|| format!("invalid 256-bit amount {}", value))
+}
+
+fn parse_account(value: &str) -> Result<[u8; 32], String> {
+    let mut bytes = [0u8; 32];
+
+    hex::decode_to_slice(value.strip_prefix("0x").unwrap_or(value), &mut bytes)
+        .map_err(|_| format!("invalid account {}, expected 32 bytes of hex", value))?;
+
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selectors_match_ink() {
+        for (message, expected) in [
+            ("bridge_out", ink_lang::selector_bytes!("bridge_out")),
+            ("bridge_in", ink_lang::selector_bytes!("bridge_in")),
+            ("refund", ink_lang::selector_bytes!("refund")),
+        ] {
+            assert_eq!(selector(message), expected);
+        }
+    }
+
+    #[test]
+    fn u256_conversions_round_trip() {
+        assert_eq!(
+            run(&["u256", "to-hex", "1000"]),
+            Ok(format!("0x{:0>64}", "3e8"))
+        );
+        assert_eq!(run(&["u256", "to-dec", "0x3e8"]), Ok("1000".to_string()));
+        assert_eq!(run(&["u256", "to-dec", "3e8"]), Ok("1000".to_string()));
+        assert!(run(&["u256", "to-hex", "12a"]).is_err());
+        assert!(run(&["u256", "to-hex", &"9".repeat(80)]).is_err());
+    }
+
+    #[test]
+    fn hash_address_matches_contract_hashing() {
+        let account = [0xa1u8; 32];
+        let expected: MultiChainAddrHash = account.as_slice().into();
+
+        assert_eq!(
+            run(&["hash-address", "--account", &hex::encode(account)]),
+            Ok(format!("0x{}", expected.to_string_digest()))
+        );
+        assert_eq!(
+            run(&["hash-address", "CHER"]),
+            Ok(format!(
+                "0x{}",
+                MultiChainAddrHash::from("CHER".to_string()).to_string_digest()
+            ))
+        );
+    }
+
+    #[test]
+    fn encode_call_appends_scale_args_to_selector() {
+        let data = encode_call("bridge_out", &["CHER", "100", "0x2"]).unwrap();
+        let mut expected = ink_lang::selector_bytes!("bridge_out").to_vec();
+
+        MultiChainAddrHash::from("CHER".to_string()).encode_to(&mut expected);
+        U256::from_u128(100).encode_to(&mut expected);
+        U256::from_u128(2).encode_to(&mut expected);
+
+        assert_eq!(data, expected);
+        assert!(encode_call("bridge_out", &["CHER", "100"]).is_err());
+        assert!(encode_call("mint", &[]).is_err());
+        assert!(encode_call("refund", &["-1"]).is_err());
+    }
+}
@@ -0,0 +1,153 @@
+use bridge_cherry_contract::bridge_cherry_contract::{BridgeRoute, ChainConfig, CherryMode};
+use bridge_cherry_contract::helpers::utils::{MultiChainAddrHash, U256};
+
+/// Minimal JSON writer for the decoded event types, so the decoder doesn't
+/// pull a serialization framework into the workspace.
+pub trait ToJson {
+    fn write_json(&self, out: &mut String);
+
+    fn to_json(&self) -> String {
+        let mut out = String::new();
+
+        self.write_json(&mut out);
+
+        out
+    }
+}
+
+pub fn write_object(out: &mut String, fields: &[(&str, &dyn ToJson)]) {
+    out.push('{');
+
+    for (i, (name, value)) in fields.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+
+        write_string(out, name);
+        out.push(':');
+        value.write_json(out);
+    }
+
+    out.push('}');
+}
+
+pub fn write_string(out: &mut String, s: &str) {
+    out.push('"');
+
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+
+    out.push('"');
+}
+
+pub fn write_hex(out: &mut String, bytes: &[u8]) {
+    write_string(out, &format!("0x{}", hex::encode(bytes)));
+}
+
+impl ToJson for String {
+    fn write_json(&self, out: &mut String) {
+        write_string(out, self);
+    }
+}
+
+impl ToJson for bool {
+    fn write_json(&self, out: &mut String) {
+        out.push_str(if *self { "true" } else { "false" });
+    }
+}
+
+macro_rules! impl_number {
+    ($($ty:ty),*) => {
+        $(
+            impl ToJson for $ty {
+                fn write_json(&self, out: &mut String) {
+                    out.push_str(&self.to_string());
+                }
+            }
+        )*
+    };
+}
+
+impl_number!(u8, u32, u64);
+
+impl ToJson for [u8; 32] {
+    fn write_json(&self, out: &mut String) {
+        write_hex(out, self);
+    }
+}
+
+/// Amounts and chain ids are written as decimal strings, since they don't
+/// fit JSON numbers.
+impl ToJson for U256 {
+    fn write_json(&self, out: &mut String) {
+        write_string(out, &self.to_decimal());
+    }
+}
+
+impl ToJson for MultiChainAddrHash {
+    fn write_json(&self, out: &mut String) {
+        write_object(
+            out,
+            &[("address", &self.to_string()), ("digest", &self.digest())],
+        );
+    }
+}
+
+impl<T: ToJson> ToJson for Option<T> {
+    fn write_json(&self, out: &mut String) {
+        match self {
+            Some(value) => value.write_json(out),
+            None => out.push_str("null"),
+        }
+    }
+}
+
+impl<T: ToJson> ToJson for Vec<T> {
+    fn write_json(&self, out: &mut String) {
+        out.push('[');
+
+        for (i, value) in self.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+
+            value.write_json(out);
+        }
+
+        out.push(']');
+    }
+}
+
+impl ToJson for BridgeRoute {
+    fn write_json(&self, out: &mut String) {
+        write_string(out, &format!("{:?}", self));
+    }
+}
+
+impl ToJson for CherryMode {
+    fn write_json(&self, out: &mut String) {
+        write_string(out, &format!("{:?}", self));
+    }
+}
+
+impl ToJson for ChainConfig {
+    fn write_json(&self, out: &mut String) {
+        write_object(
+            out,
+            &[
+                ("fee_bps", &self.fee_bps),
+                ("cherry_fee_bps", &self.cherry_fee_bps),
+                ("cherry_mode", &self.cherry_mode),
+            ],
+        );
+    }
+}
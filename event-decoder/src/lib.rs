@@ -0,0 +1,307 @@
+//! Decodes raw `Bridge` contract events into typed structs and JSON.
+//!
+//! Contract events are SCALE-encoded as a single enum whose variant index is
+//! the event's declaration order in the contract, followed by every field in
+//! declaration order, topics included. The mirrors below must therefore list
+//! events and fields exactly as the contract declares them.
+
+pub mod json;
+
+use std::fmt;
+
+use scale::{Decode, Encode};
+
+pub use bridge_cherry_contract::bridge_cherry_contract::{BridgeRoute, ChainConfig, CherryMode};
+pub use bridge_cherry_contract::helpers::utils::{MultiChainAddrHash, U256};
+
+use json::ToJson;
+
+macro_rules! events {
+    ($($name:ident { $($field:ident: $ty:ty),* $(,)? } topics [$($topic:literal),*];)*) => {
+        $(
+            #[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+            pub struct $name {
+                $(pub $field: $ty),*
+            }
+
+            impl ToJson for $name {
+                fn write_json(&self, out: &mut String) {
+                    json::write_object(out, &[$((stringify!($field), &self.$field as &dyn ToJson)),*]);
+                }
+            }
+        )*
+
+        #[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+        pub enum BridgeEvent {
+            $($name($name)),*
+        }
+
+        impl BridgeEvent {
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $(BridgeEvent::$name(_) => stringify!($name)),*
+                }
+            }
+
+            /// Names of the event's topic fields, in the order they follow
+            /// the event signature topic.
+            pub fn topic_names(&self) -> &'static [&'static str] {
+                match self {
+                    $(BridgeEvent::$name(_) => &[$($topic),*]),*
+                }
+            }
+        }
+
+        impl ToJson for BridgeEvent {
+            fn write_json(&self, out: &mut String) {
+                match self {
+                    $(BridgeEvent::$name(event) => event.write_json(out)),*
+                }
+            }
+        }
+    };
+}
+
+events! {
+    ChainConfigured {
+        chain_id: U256,
+        config: Option<ChainConfig>,
+    } topics ["chain_id"];
+    CherryTokenUpdated {
+        old_token: MultiChainAddrHash,
+        new_token: MultiChainAddrHash,
+    } topics ["old_token", "new_token"];
+    BridgedOut {
+        version: u8,
+        token: [u8; 32],
+        sender: [u8; 32],
+        target_chain: U256,
+        nonce: u64,
+        token_address: MultiChainAddrHash,
+        sender_address: MultiChainAddrHash,
+        recipient: Option<MultiChainAddrHash>,
+        amount: U256,
+        fee: U256,
+        route: BridgeRoute,
+        expires_at: u64,
+    } topics ["token", "sender", "target_chain"];
+    BridgedIn {
+        version: u8,
+        token: [u8; 32],
+        recipient: [u8; 32],
+        source_chain: U256,
+        source_nonce: Option<u64>,
+        token_address: MultiChainAddrHash,
+        recipient_address: MultiChainAddrHash,
+        amount: U256,
+        fee: U256,
+        route: BridgeRoute,
+    } topics ["token", "recipient", "source_chain"];
+    BridgeCherrySimple {
+        token_amount: String,
+        recipient: String,
+    } topics [];
+    BridgeInSimple {
+        token_address: String,
+        token_amount: String,
+        recipient: String,
+        from_chain: Option<String>,
+    } topics [];
+    BridgeOutSimple {
+        token_address: String,
+        token_amount: String,
+        recipient: String,
+        target_chain: Option<String>,
+    } topics [];
+    BridgeOutFailed {
+        nonce: u64,
+    } topics ["nonce"];
+    BridgeOutRefunded {
+        nonce: u64,
+        token_address: MultiChainAddrHash,
+        token_amount: U256,
+        fee: U256,
+        recipient: MultiChainAddrHash,
+    } topics ["nonce", "token_address", "recipient"];
+    PausedChanged {
+        paused: bool,
+    } topics [];
+    Initiate {
+        initiated: bool,
+        by: String,
+        initial_balance: String,
+    } topics [];
+    TokenCreated {
+        token: String,
+        by: String,
+        recipient: String,
+        initial_balance: String,
+        decimals: u8,
+        name: String,
+        symbol: String,
+    } topics ["token"];
+    Approval {
+        owner: String,
+        spender: String,
+        value_decimal: String,
+        value_hex: String,
+    } topics ["owner", "spender"];
+    Transfer {
+        from: Option<String>,
+        to: Option<String>,
+        value_decimal: String,
+        value_hex: String,
+    } topics ["from", "to"];
+    TransferBatch {
+        from: Option<String>,
+        to: Option<String>,
+        tokens: Vec<String>,
+        values_decimal: Vec<String>,
+        values_hex: Vec<String>,
+    } topics ["from", "to"];
+    ApprovalForAll {
+        owner: String,
+        operator: String,
+        approved: bool,
+    } topics ["owner", "operator"];
+    BridgeInBatch {
+        total: u32,
+        succeeded: u32,
+        failed: u32,
+    } topics [];
+    BridgeOutBatch {
+        total: u32,
+        succeeded: u32,
+        failed: u32,
+    } topics [];
+    UpgradeScheduled {
+        code_hash: [u8; 32],
+        ready_at: u64,
+    } topics ["code_hash"];
+    Upgraded {
+        code_hash: [u8; 32],
+        storage_version: u32,
+    } topics ["code_hash"];
+    Migrated {
+        from_version: u32,
+        to_version: u32,
+    } topics [];
+    StateImported {
+        chunks: u32,
+        checksum: [u8; 32],
+    } topics [];
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    /// A field of the input line isn't valid hex.
+    Hex(String),
+    /// A topic isn't exactly 32 bytes long.
+    TopicLength(usize),
+    /// The data isn't a SCALE-encoded contract event.
+    Scale(String),
+    /// The event was decoded but bytes were left over.
+    TrailingBytes(usize),
+    /// The topic count doesn't match the decoded event.
+    TopicCount { expected: usize, found: usize },
+    /// The input line is empty.
+    Empty,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::Hex(e) => write!(f, "invalid hex: {}", e),
+            DecodeError::TopicLength(len) => write!(f, "topic is {} bytes, expected 32", len),
+            DecodeError::Scale(e) => write!(f, "invalid event data: {}", e),
+            DecodeError::TrailingBytes(len) => write!(f, "{} trailing bytes after event", len),
+            DecodeError::TopicCount { expected, found } => {
+                write!(f, "expected {} topics, found {}", expected, found)
+            }
+            DecodeError::Empty => write!(f, "empty input"),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// An event along with the topics it was emitted with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedEvent {
+    pub event: BridgeEvent,
+    pub topics: Vec<[u8; 32]>,
+}
+
+impl ToJson for DecodedEvent {
+    /// `{"event": name, "topics": {...}, "fields": {...}}`, where topics are
+    /// keyed by name with the event signature topic first.
+    fn write_json(&self, out: &mut String) {
+        let names = core::iter::once("signature").chain(self.event.topic_names().iter().copied());
+        let topics: Vec<(&str, &dyn ToJson)> = names
+            .zip(self.topics.iter())
+            .map(|(name, topic)| (name, topic as &dyn ToJson))
+            .collect();
+
+        out.push('{');
+        json::write_string(out, "event");
+        out.push(':');
+        json::write_string(out, self.event.name());
+        out.push_str(",\"topics\":");
+        json::write_object(out, &topics);
+        out.push_str(",\"fields\":");
+        self.event.write_json(out);
+        out.push('}');
+    }
+}
+
+/// Decodes the data of a single contract event.
+pub fn decode_event(data: &[u8]) -> Result<BridgeEvent, DecodeError> {
+    let mut input = data;
+    let event = BridgeEvent::decode(&mut input).map_err(|e| DecodeError::Scale(e.to_string()))?;
+
+    if !input.is_empty() {
+        return Err(DecodeError::TrailingBytes(input.len()));
+    }
+
+    Ok(event)
+}
+
+/// Decodes an event together with its topics. Topics may be omitted
+/// altogether; when present there must be one per topic field plus the
+/// event signature topic.
+pub fn decode_with_topics(data: &[u8], topics: Vec<[u8; 32]>) -> Result<DecodedEvent, DecodeError> {
+    let event = decode_event(data)?;
+    let expected = event.topic_names().len() + 1;
+
+    if !topics.is_empty() && topics.len() != expected {
+        return Err(DecodeError::TopicCount {
+            expected,
+            found: topics.len(),
+        });
+    }
+
+    Ok(DecodedEvent { event, topics })
+}
+
+/// Decodes one input line: the hex event data, optionally followed by the
+/// hex topics, separated by whitespace. Hex may be `0x`-prefixed.
+pub fn decode_line(line: &str) -> Result<DecodedEvent, DecodeError> {
+    let mut fields = line.split_whitespace();
+    let data = parse_hex(fields.next().ok_or(DecodeError::Empty)?)?;
+    let topics = fields
+        .map(|field| {
+            let bytes = parse_hex(field)?;
+
+            <[u8; 32]>::try_from(bytes.as_slice())
+                .map_err(|_| DecodeError::TopicLength(bytes.len()))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    decode_with_topics(&data, topics)
+}
+
+fn parse_hex(s: &str) -> Result<Vec<u8>, DecodeError> {
+    let s = s.strip_prefix("0x").unwrap_or(s);
+
+    hex::decode(s).map_err(|e| DecodeError::Hex(e.to_string()))
+}
@@ -0,0 +1,70 @@
+//! Reads raw `Bridge` events, one per line, from a file or stdin and prints
+//! each as a JSON line.
+//!
+//! Each input line holds the hex event data, optionally followed by its hex
+//! topics, separated by whitespace. Blank lines and lines starting with `#`
+//! are skipped. Lines that fail to decode are reported on stderr and make the
+//! process exit with status 1 once the input is exhausted.
+//!
+//! ```text
+//! bridge-events [FILE]
+//! ```
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::ExitCode;
+
+use bridge_event_decoder::decode_line;
+use bridge_event_decoder::json::ToJson;
+
+fn main() -> ExitCode {
+    let input: Box<dyn BufRead> = match std::env::args().nth(1) {
+        Some(path) if path != "-" => match File::open(&path) {
+            Ok(file) => Box::new(BufReader::new(file)),
+            Err(e) => {
+                eprintln!("bridge-events: {}: {}", path, e);
+
+                return ExitCode::from(2);
+            }
+        },
+        _ => Box::new(BufReader::new(io::stdin())),
+    };
+
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let mut failed = false;
+
+    for (number, line) in input.lines().enumerate() {
+        let line = match line {
+            Ok(line) => line,
+            Err(e) => {
+                eprintln!("bridge-events: {}", e);
+
+                return ExitCode::from(2);
+            }
+        };
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        match decode_line(line) {
+            Ok(event) => {
+                if writeln!(out, "{}", event.to_json()).is_err() {
+                    return ExitCode::from(2);
+                }
+            }
+            Err(e) => {
+                eprintln!("bridge-events: line {}: {}", number + 1, e);
+                failed = true;
+            }
+        }
+    }
+
+    if failed {
+        ExitCode::from(1)
+    } else {
+        ExitCode::SUCCESS
+    }
+}
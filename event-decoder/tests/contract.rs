@@ -0,0 +1,200 @@
+//! Decodes events recorded from the contract itself. The golden vectors are
+//! encoded from the decoder's own mirror, so only these tests catch the mirror
+//! drifting from the contract's `Event` enum.
+
+use std::collections::BTreeSet;
+
+use bridge_cherry_contract::bridge_cherry_contract::{
+    BatchMode, Bridge, ChainConfig, CherryMode, StateSection, TokenAllocation,
+};
+use bridge_cherry_contract::helpers::utils::{MultiChainAddrHash, U256};
+use bridge_event_decoder::decode_with_topics;
+use ink_env::{test, AccountId, DefaultEnvironment, Hash};
+use ink_lang::reflect::ContractEventBase;
+use scale::{Decode, Encode};
+
+type ContractEvent = <Bridge as ContractEventBase>::Type;
+
+/// The contract's name for `event`. The match is exhaustive, so an event
+/// added to the contract fails to compile here until it is mirrored.
+fn contract_event_name(event: &ContractEvent) -> &'static str {
+    match event {
+        ContractEvent::ChainConfigured(_) => "ChainConfigured",
+        ContractEvent::CherryTokenUpdated(_) => "CherryTokenUpdated",
+        ContractEvent::BridgedOut(_) => "BridgedOut",
+        ContractEvent::BridgedIn(_) => "BridgedIn",
+        ContractEvent::BridgeCherrySimple(_) => "BridgeCherrySimple",
+        ContractEvent::BridgeInSimple(_) => "BridgeInSimple",
+        ContractEvent::BridgeOutSimple(_) => "BridgeOutSimple",
+        ContractEvent::BridgeOutFailed(_) => "BridgeOutFailed",
+        ContractEvent::BridgeOutRefunded(_) => "BridgeOutRefunded",
+        ContractEvent::PausedChanged(_) => "PausedChanged",
+        ContractEvent::Initiate(_) => "Initiate",
+        ContractEvent::TokenCreated(_) => "TokenCreated",
+        ContractEvent::Approval(_) => "Approval",
+        ContractEvent::Transfer(_) => "Transfer",
+        ContractEvent::TransferBatch(_) => "TransferBatch",
+        ContractEvent::ApprovalForAll(_) => "ApprovalForAll",
+        ContractEvent::BridgeInBatch(_) => "BridgeInBatch",
+        ContractEvent::BridgeOutBatch(_) => "BridgeOutBatch",
+        ContractEvent::UpgradeScheduled(_) => "UpgradeScheduled",
+        ContractEvent::Upgraded(_) => "Upgraded",
+        ContractEvent::Migrated(_) => "Migrated",
+        ContractEvent::StateImported(_) => "StateImported",
+    }
+}
+
+fn hash(s: &str) -> MultiChainAddrHash {
+    MultiChainAddrHash::from(s.to_string())
+}
+
+fn account_hash(account: AccountId) -> MultiChainAddrHash {
+    (account.as_ref() as &[u8]).into()
+}
+
+fn amount(value: u128) -> U256 {
+    U256::from_u128(value)
+}
+
+/// Calls every message that emits an event, except `upgrade` and `migrate`,
+/// which need a code swap the off-chain environment cannot do.
+fn emit_events() {
+    let accounts = test::default_accounts::<DefaultEnvironment>();
+    let bob = account_hash(accounts.bob);
+    let token = hash("TOKEN");
+    let cherry = hash("CHER");
+    let chain = amount(2);
+    let custody = account_hash(test::callee::<DefaultEnvironment>());
+
+    test::set_caller::<DefaultEnvironment>(accounts.alice);
+
+    let mut bridge = Bridge::new(token.clone(), amount(1_000), hash("OLD_CHER"));
+
+    bridge.set_cherry_token(cherry.clone()).unwrap();
+    bridge
+        .create_token(TokenAllocation {
+            token: cherry.clone(),
+            supply: amount(1_000),
+            recipient: account_hash(accounts.alice),
+            metadata: Default::default(),
+        })
+        .unwrap();
+    bridge
+        .set_chain(
+            chain,
+            Some(ChainConfig {
+                fee_bps: 0,
+                cherry_fee_bps: 0,
+                cherry_mode: CherryMode::Lock,
+            }),
+        )
+        .unwrap();
+    bridge.approve(bob.clone(), token.clone(), amount(5));
+    bridge.approve(custody.clone(), token.clone(), amount(200));
+    bridge.approve(custody, cherry, amount(50));
+    bridge
+        .transfer(bob.clone(), token.clone(), amount(10))
+        .unwrap();
+    bridge
+        .transfer_batch(bob.clone(), vec![(token.clone(), amount(1))])
+        .unwrap();
+    bridge.set_approval_for_all(bob.clone(), true);
+    bridge.set_legacy_events(true).unwrap();
+    bridge
+        .bridge_out(token.clone(), amount(100), chain)
+        .unwrap();
+    bridge
+        .bridge_out(token.clone(), amount(100), chain)
+        .unwrap();
+    bridge
+        .bridge_cherry(amount(50), chain, hash("0xdead"))
+        .unwrap();
+    bridge
+        .bridge_in(token.clone(), amount(20), bob, chain, 0)
+        .unwrap();
+    bridge
+        .bridge_in_batch(Vec::new(), BatchMode::BestEffort)
+        .unwrap();
+    bridge
+        .bridge_out_batch(Vec::new(), BatchMode::BestEffort)
+        .unwrap();
+    bridge.mark_failed(0).unwrap();
+    bridge.refund(0).unwrap();
+    bridge.schedule_upgrade(Hash::from([1; 32])).unwrap();
+    bridge.set_paused(true).unwrap();
+
+    let chunks = vec![bridge.export_state(StateSection::Tokens, 0, 10)];
+
+    test::set_callee::<DefaultEnvironment>(accounts.django);
+
+    let mut imported = Bridge::new_with_allocations(Vec::new(), hash("CHER"));
+
+    imported
+        .begin_import(Bridge::state_checksum(&chunks))
+        .unwrap();
+    imported.import_state(chunks).unwrap();
+    imported.finalize_import().unwrap();
+}
+
+#[test]
+fn contract_events_decode_with_the_contract_variant() {
+    ink_env::test::run_test::<DefaultEnvironment, _>(|_| {
+        emit_events();
+
+        let mut seen = BTreeSet::new();
+
+        for recorded in test::recorded_events() {
+            let contract_event = ContractEvent::decode(&mut &recorded.data[..]).unwrap();
+            let name = contract_event_name(&contract_event);
+            let topics = recorded
+                .topics
+                .iter()
+                .map(|topic| <[u8; 32]>::try_from(topic.as_slice()).unwrap())
+                .collect();
+            let decoded = decode_with_topics(&recorded.data, topics)
+                .unwrap_or_else(|e| panic!("{}: {}", name, e));
+
+            assert_eq!(decoded.event.name(), name);
+            assert_eq!(decoded.event.encode()[0], recorded.data[0], "{}", name);
+            assert_eq!(decoded.event.encode(), recorded.data, "{}", name);
+            assert_eq!(
+                decoded.topics.len(),
+                decoded.event.topic_names().len() + 1,
+                "{}",
+                name
+            );
+
+            seen.insert(name);
+        }
+
+        let expected: BTreeSet<&str> = [
+            "ChainConfigured",
+            "CherryTokenUpdated",
+            "BridgedOut",
+            "BridgedIn",
+            "BridgeCherrySimple",
+            "BridgeInSimple",
+            "BridgeOutSimple",
+            "BridgeOutFailed",
+            "BridgeOutRefunded",
+            "PausedChanged",
+            "Initiate",
+            "TokenCreated",
+            "Approval",
+            "Transfer",
+            "TransferBatch",
+            "ApprovalForAll",
+            "BridgeInBatch",
+            "BridgeOutBatch",
+            "UpgradeScheduled",
+            "StateImported",
+        ]
+        .into_iter()
+        .collect();
+
+        assert_eq!(seen, expected);
+
+        Ok(())
+    })
+    .unwrap();
+}
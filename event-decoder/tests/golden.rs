@@ -0,0 +1,68 @@
+use bridge_event_decoder::json::ToJson;
+use bridge_event_decoder::{decode_event, decode_line, BridgeEvent, DecodeError};
+use scale::Encode;
+
+const EVENTS: &str = include_str!("golden/events.txt");
+const EXPECTED: &str = include_str!("golden/events.jsonl");
+
+fn event_lines() -> impl Iterator<Item = &'static str> {
+    EVENTS
+        .lines()
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+}
+
+#[test]
+fn golden_events_decode_to_expected_json() {
+    let decoded: Vec<String> = event_lines()
+        .map(|line| decode_line(line).unwrap().to_json())
+        .collect();
+    let expected: Vec<&str> = EXPECTED.lines().collect();
+
+    assert_eq!(decoded.len(), expected.len());
+
+    for (decoded, expected) in decoded.iter().zip(expected) {
+        assert_eq!(decoded, expected);
+    }
+}
+
+#[test]
+fn golden_events_round_trip() {
+    for line in event_lines() {
+        let data = hex::decode(line.split_whitespace().next().unwrap()).unwrap();
+
+        assert_eq!(decode_event(&data).unwrap().encode(), data);
+    }
+}
+
+#[test]
+fn golden_events_cover_the_versioned_schema() {
+    let names: Vec<&str> = event_lines()
+        .map(|line| decode_line(line).unwrap().event.name())
+        .collect();
+
+    for name in ["BridgedOut", "BridgedIn", "BridgeOutRefunded", "Transfer"] {
+        assert!(names.contains(&name), "missing {}", name);
+    }
+}
+
+#[test]
+fn malformed_input_is_rejected() {
+    let paused = BridgeEvent::PausedChanged(bridge_event_decoder::PausedChanged { paused: true });
+    let mut data = paused.encode();
+
+    assert_eq!(decode_event(&data), Ok(paused));
+
+    data.push(0);
+    assert_eq!(decode_event(&data), Err(DecodeError::TrailingBytes(1)));
+    assert!(matches!(decode_event(&[0xff]), Err(DecodeError::Scale(_))));
+    assert!(matches!(decode_line("0xzz"), Err(DecodeError::Hex(_))));
+    assert_eq!(decode_line("   "), Err(DecodeError::Empty));
+    assert_eq!(decode_line("0901 00"), Err(DecodeError::TopicLength(1)));
+    assert_eq!(
+        decode_line(&format!("0901 {} {}", "00".repeat(32), "00".repeat(32))),
+        Err(DecodeError::TopicCount {
+            expected: 1,
+            found: 2
+        })
+    );
+}
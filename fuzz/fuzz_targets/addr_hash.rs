@@ -0,0 +1,26 @@
+//! Building a `MultiChainAddrHash` from arbitrary bytes or strings must not
+//! panic, its digest must always be the hash of its address, and raw account
+//! bytes must map back to the same account.
+
+#![no_main]
+
+use bridge_cherry_contract::helpers::utils::MultiChainAddrHash;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let hash: MultiChainAddrHash = data.into();
+
+    assert_eq!(
+        hash.digest(),
+        MultiChainAddrHash::make_hash(hash.to_string().as_bytes())
+    );
+
+    if let Ok(account) = <[u8; 32]>::try_from(data) {
+        assert_eq!(hash.to_account_bytes(), Some(account));
+    }
+
+    let hash = MultiChainAddrHash::from(String::from_utf8_lossy(data).into_owned());
+
+    let _ = hash.to_string_digest();
+    let _ = hash.to_account_bytes();
+});
@@ -0,0 +1,39 @@
+//! Calls every message that takes its amounts, chains and addresses as
+//! strings with arbitrary input. Malformed strings must come back as errors.
+
+#![no_main]
+
+use bridge_cherry_contract::bridge_cherry_contract::Bridge;
+use bridge_cherry_contract::helpers::utils::{MultiChainAddrHash, U256};
+use ink_env::{test, DefaultEnvironment};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|args: [String; 4]| {
+    let [token, amount, address, chain] = args;
+
+    test::run_test::<DefaultEnvironment, _>(|accounts| {
+        test::set_callee::<DefaultEnvironment>([0x07; 32].into());
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+
+        let mut bridge = Bridge::new(
+            MultiChainAddrHash::from(token.clone()),
+            U256::from_u128(1_000),
+            MultiChainAddrHash::from("CHER".to_string()),
+        );
+
+        let _ = bridge.bridge_in_string(
+            token.clone(),
+            amount.clone(),
+            address.clone(),
+            chain.clone(),
+            0,
+        );
+        let _ = bridge.bridge_out_string(token.clone(), amount.clone(), chain.clone());
+        let _ = bridge.bridge_cherry_string(amount, chain, address.clone());
+        let _ = bridge.get_balance_of(token.clone(), address.clone());
+        let _ = bridge.get_allowance_of(address.clone(), address, token);
+
+        Ok(())
+    })
+    .unwrap();
+});
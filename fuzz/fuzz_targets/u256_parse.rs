@@ -0,0 +1,25 @@
+//! `U256` parsing must reject malformed input instead of panicking, and
+//! whatever it accepts must survive a decimal and hex round trip.
+
+#![no_main]
+
+use bridge_cherry_contract::helpers::utils::U256;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Some(value) = U256::from_hex(data) {
+        assert_eq!(U256::from_hex(value.to_hex().as_bytes()), Some(value));
+    }
+
+    let Ok(s) = core::str::from_utf8(data) else {
+        return;
+    };
+
+    if let Some(value) = U256::parse(s) {
+        assert_eq!(U256::from_decimal(&value.to_decimal()), Some(value));
+        assert_eq!(U256::parse(&format!("0x{}", value.to_hex())), Some(value));
+    }
+
+    let _ = U256::from_decimal(s);
+    let _ = U256::try_from(s.to_string());
+});
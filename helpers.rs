@@ -7,16 +7,19 @@ pub mod utils {
     use scale::{Decode, Encode};
     use scale_info::TypeInfo;
 
-    fn hex_decode(s: &[u8]) -> [u8; 32] {
-        let res = hex::decode(s).unwrap();
+    /// Decodes up to 64 hex digits into a big-endian, zero-padded buffer.
+    fn hex_decode(s: &[u8]) -> Option<[u8; 32]> {
+        if s.len() > 64 {
+            return None;
+        }
 
+        let mut padded = [b'0'; 64];
         let mut buffer = [0u8; 32];
 
-        for i in (0..res.len()).rev() {
-            buffer[32 - (res.len() - i)] = res[i];
-        }
+        padded[64 - s.len()..].copy_from_slice(s);
+        hex::decode_to_slice(padded, &mut buffer).ok()?;
 
-        buffer
+        Some(buffer)
     }
 
     fn hex_encode(h: &[u8]) -> String {
@@ -33,6 +36,10 @@ pub mod utils {
         Encode,
         Decode,
         Clone,
+        PartialEq,
+        Eq,
+        PartialOrd,
+        Ord,
     )]
     pub struct MultiChainAddrHash {
         digest: [u8; 32],
@@ -59,6 +66,10 @@ pub mod utils {
             }
         }
 
+        pub fn digest(&self) -> [u8; 32] {
+            self.digest
+        }
+
         pub fn to_string_digest(&self) -> String {
             let digest = self.digest;
             let digest_slice = digest.as_slice();
@@ -66,27 +77,39 @@ pub mod utils {
             hex_encode(digest_slice)
         }
 
-        pub fn to_string(&self) -> String {
-            self.address_str.clone()
+        pub fn to_account_bytes(&self) -> Option<[u8; 32]> {
+            if let Some(hex_str) = self.address_str.strip_prefix("0x") {
+                let mut bytes = [0u8; 32];
+
+                if hex::decode_to_slice(hex_str, &mut bytes).is_ok() {
+                    return Some(bytes);
+                }
+            }
+
+            self.address_str.as_bytes().try_into().ok()
+        }
+    }
+
+    impl core::fmt::Display for MultiChainAddrHash {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            f.write_str(&self.address_str)
         }
     }
 
     impl From<String> for MultiChainAddrHash {
         fn from(s: String) -> Self {
-            assert!(
-                s.len() > 34 && s.len() < 32,
-                "Length must be between 32 and 34"
-            );
-
-            Self::from(s)
+            MultiChainAddrHash::from(s)
         }
     }
 
     impl From<&[u8]> for MultiChainAddrHash {
+        /// Raw account bytes that aren't valid UTF-8 are addressed by their
+        /// `0x`-prefixed hex form, which `to_account_bytes` decodes back.
         fn from(b: &[u8]) -> Self {
-            let multi_addr = core::str::from_utf8(b).unwrap();
-
-            Self::from(multi_addr.to_string())
+            match core::str::from_utf8(b) {
+                Ok(multi_addr) => Self::from(multi_addr.to_string()),
+                Err(_) => Self::from(format!("0x{}", hex_encode(b))),
+            }
         }
     }
 
@@ -102,14 +125,16 @@ pub mod utils {
         TypeInfo,
         Encode,
         Decode,
+        PartialEq,
+        Eq,
     )]
     pub struct U256([u8; 32]);
 
     impl U256 {
-        pub fn from_hex(b: &[u8]) -> Self {
-            let buffer = hex_decode(b);
-
-            U256(buffer)
+        /// Reads up to 64 hex digits without a prefix, returning `None` for
+        /// anything else.
+        pub fn from_hex(b: &[u8]) -> Option<Self> {
+            hex_decode(b).map(Self)
         }
 
         pub fn get(&self) -> &[u8; 32] {
@@ -118,146 +143,57 @@ pub mod utils {
             b
         }
 
-        pub fn from_decimal(s: String) -> Self {
-            fn add(s1: String, s2: String) -> String {
-                let (mut result, mut stmp) = ("".to_string(), "".to_string());
-                let mut mark = 0;
-
-                fn string_to_array_reversed(s: String) -> [u32; 256] {
-                    let mut chars = s.chars();
-
-                    let mut ret = [03u32; 256];
-
-                    for i in 0..chars.clone().count() {
-                        ret[i] = chars.next_back().unwrap().to_digit(10).unwrap();
-                    }
-
-                    ret
-                }
-
-                let smaller = if s1.len() > s2.len() {
-                    s2.clone()
-                } else {
-                    s1.clone()
-                };
-                let larger = if s1.len() > s2.len() {
-                    s1.clone()
-                } else {
-                    s2.clone()
-                };
-
-                let a1 = string_to_array_reversed(larger.clone());
-                let a2 = string_to_array_reversed(smaller.clone());
-
-                for i in 0..larger.len() {
-                    let ctmp = a1[i] + (if i < smaller.len() { a2[i] } else { 0 }) + mark;
-
-                    match ctmp {
-                        0 => {
-                            stmp = "0".to_string();
-                            mark = 0;
-                        }
-                        1 => {
-                            stmp = "1".to_string();
-                            mark = 0;
-                        }
-                        2 => {
-                            stmp = "0".to_string();
-                            mark = 1;
-                        }
-                        3 => {
-                            stmp = "1".to_string();
-                            mark = 1;
-                        }
-                        _ => (),
-                    }
+        /// Reads a decimal number, returning `None` for an empty string,
+        /// non-digits or values that don't fit in 256 bits.
+        pub fn from_decimal(s: &str) -> Option<Self> {
+            if s.is_empty() {
+                return None;
+            }
 
-                    let mut r = "".to_string();
-                    r.push_str(stmp.as_str());
-                    r.push_str(result.as_str());
+            let mut buffer = [0u8; 32];
 
-                    result = r;
-                }
+            for c in s.chars() {
+                let mut carry = c.to_digit(10)?;
 
-                if mark > 0 {
-                    let mut r = "1".to_string();
-                    r.push_str(result.as_str());
+                for byte in buffer.iter_mut().rev() {
+                    let value = *byte as u32 * 10 + carry;
 
-                    result = r
+                    *byte = value as u8;
+                    carry = value >> 8;
                 }
 
-                result
-            }
-
-            fn digit_to_bin(c: char) -> &'static str {
-                match c {
-                    '0' => "0",
-                    '1' => "1",
-                    '2' => "10",
-                    '3' => "11",
-                    '4' => "100",
-                    '5' => "101",
-                    '6' => "110",
-                    '7' => "111",
-                    '8' => "1000",
-                    '9' => "1001",
-                    _ => "",
+                if carry != 0 {
+                    return None;
                 }
             }
 
-            let mut result = "".to_string();
-            let mut s_chars = s.chars();
-
-            for _ in 0..s.len() {
-                let mut r1 = result.clone();
-                let mut r2 = result.clone();
-
-                r1.push_str("0");
-                r2.push_str("000");
-
-                result = add(r1, r2);
-                result = add(result, digit_to_bin(s_chars.next().unwrap()).to_string());
-            }
-
-            let diff = 256 - result.len();
-
-            let mut r = "".to_string();
-
-            for _ in 0..diff {
-                r.push_str("0");
-            }
-
-            r.push_str(result.as_str());
-
-            result = r;
-
-            let mut buffer = [0u8; 32];
-
-            for i in (0..256).step_by(8) {
-                let sub_string = &result[i..i + 8];
-
-                let byte = u8::from_str_radix(sub_string, 2).unwrap();
+            Some(Self(buffer))
+        }
 
-                buffer[i / 8] = byte;
+        /// Parses a `0x`-prefixed hex or a decimal string, returning `None` for
+        /// malformed input or values that don't fit in 256 bits.
+        pub fn parse(s: &str) -> Option<Self> {
+            match s.strip_prefix("0x") {
+                Some("") => None,
+                Some(hex_str) => Self::from_hex(hex_str.as_bytes()),
+                None => Self::from_decimal(s),
             }
-
-            Self(buffer)
         }
 
-        pub fn to_decimal(&self) -> String {
+        pub fn to_decimal(self) -> String {
             let b = self.get();
 
             let mut digits = [0u16; 78];
             let mut length = 1;
 
-            for j in 0..32 {
-                let (mut i, mut carry) = (0usize, b[j] as u16);
+            for byte in b.iter() {
+                let (mut i, mut carry) = (0usize, *byte as u16);
 
                 while i < length || carry != 0 {
                     let mut value = digits[i] * 256 + carry;
 
                     carry = value / 10;
-                    value = value % 10;
+                    value %= 10;
 
                     digits[i] = value;
 
@@ -270,7 +206,6 @@ pub mod utils {
             }
 
             let mut number = "".to_string();
-            println!("l {length}");
             for k in (0..length).rev() {
                 number.push_str(digits[k].to_string().as_str());
             }
@@ -278,30 +213,79 @@ pub mod utils {
             number
         }
 
-        pub fn to_hex(&self) -> String {
-            let arr = self.get();
+        pub fn from_u128(v: u128) -> Self {
+            let mut buffer = [0u8; 32];
 
-            hex_encode(arr as &[u8])
+            buffer[16..].copy_from_slice(&v.to_be_bytes());
+
+            Self(buffer)
         }
 
-        pub fn a_greater_than_b(a: &Self, b: &Self) -> bool {
-            let a_arr = a.get();
-            let b_arr = b.get();
+        pub fn to_u128(self) -> Option<u128> {
+            let b = self.get();
+
+            if b[..16].iter().any(|byte| *byte != 0) {
+                return None;
+            }
+
+            let mut low = [0u8; 16];
+            low.copy_from_slice(&b[16..]);
+
+            Some(u128::from_be_bytes(low))
+        }
+
+        /// Takes `bps` basis points of the value, rounding down. Works over
+        /// the full 256 bits and only fails when `bps` is above 10_000 and
+        /// the result no longer fits.
+        pub fn apply_bps(self, bps: u32) -> Option<Self> {
+            let b = self.get();
+
+            let mut whole = [0u8; 32];
+            let mut rem = 0u32;
+
+            for (q, byte) in whole.iter_mut().zip(b.iter()) {
+                let cur = rem * 256 + *byte as u32;
 
-            let mut score_a = 0u32;
-            let mut score_b = 0u32;
+                *q = (cur / 10_000) as u8;
+                rem = cur % 10_000;
+            }
+
+            let mut carry = 0u64;
 
-            for i in 0usize..32usize {
-                let a_least_significant = a_arr[i];
-                let b_least_significant = b_arr[i];
+            for q in whole.iter_mut().rev() {
+                let prod = *q as u64 * bps as u64 + carry;
 
-                score_a += (a_least_significant > b_least_significant) as u32;
-                score_b += (b_least_significant > a_least_significant) as u32;
+                *q = prod as u8;
+                carry = prod >> 8;
             }
 
-            score_a > score_b
+            if carry != 0 {
+                return None;
+            }
+
+            let whole = Self(whole);
+            let part = Self::from_u128(rem as u128 * bps as u128 / 10_000);
+            let sum = Self::add_a_with_b(&whole, &part);
+
+            if Self::a_greater_than_b(&whole, &sum) {
+                return None;
+            }
+
+            Some(sum)
+        }
+
+        pub fn to_hex(self) -> String {
+            let arr = self.get();
+
+            hex_encode(arr as &[u8])
+        }
+
+        /// Compares the big-endian bytes, most significant first.
+        pub fn a_greater_than_b(a: &Self, b: &Self) -> bool {
+            a.get() > b.get()
         }
 
+        /// Adds `b` to `a`, wrapping around at 2^256.
         pub fn add_a_with_b(a: &Self, b: &Self) -> Self {
             let a_arr = a.get();
             let b_arr = b.get();
@@ -309,53 +293,41 @@ pub mod utils {
             let mut addition_arr = [0u8; 32];
             let mut carry = 0u16;
 
-            for i in 0usize..32usize {
-                let a_least_significant = a_arr[i] as u16;
-                let b_least_significant = b_arr[i] as u16;
-
-                let apbpc = a_least_significant + b_least_significant + carry;
+            for i in (0usize..32usize).rev() {
+                let apbpc = a_arr[i] as u16 + b_arr[i] as u16 + carry;
 
-                match apbpc > u8::MAX as u16 {
-                    true => {
-                        addition_arr[i] = (apbpc - (u8::MAX as u16)) as u8;
-                        carry = 1;
-                    }
-                    false => {
-                        addition_arr[i] = apbpc as u8;
-                        carry = 0;
-                    }
-                }
+                addition_arr[i] = apbpc as u8;
+                carry = apbpc >> 8;
             }
 
             Self(addition_arr)
         }
 
+        /// Subtracts `b` from `a`, wrapping around at 2^256. Callers check
+        /// `a_greater_than_b` first where underflow matters.
         pub fn subtract_b_from_a(a: &Self, b: &Self) -> Self {
             let a_arr = a.get();
             let b_arr = b.get();
 
-            let mut addition_arr = [0u8; 32];
-            let mut carry = 0i16;
+            let mut subtraction_arr = [0u8; 32];
+            let mut borrow = 0i16;
 
-            for i in 0usize..32usize {
-                let a_least_significant = a_arr[i] as i16;
-                let b_least_significant = b_arr[i] as i16;
+            for i in (0usize..32usize).rev() {
+                let ambmc = a_arr[i] as i16 - b_arr[i] as i16 - borrow;
 
-                let apbpc = a_least_significant - b_least_significant - carry;
-
-                match apbpc < u8::MIN as i16 {
+                match ambmc < 0 {
                     true => {
-                        addition_arr[i] = ((u8::MAX as i16) + apbpc) as u8;
-                        carry = 1;
+                        subtraction_arr[i] = (ambmc + 256) as u8;
+                        borrow = 1;
                     }
                     false => {
-                        addition_arr[i] = apbpc as u8;
-                        carry = 0;
+                        subtraction_arr[i] = ambmc as u8;
+                        borrow = 0;
                     }
                 }
             }
 
-            Self(addition_arr)
+            Self(subtraction_arr)
         }
 
         pub fn new_zero() -> Self {
@@ -371,15 +343,249 @@ pub mod utils {
         }
     }
 
-    impl From<String> for U256 {
-        fn from(s: String) -> Self {
-            match s.chars().next().unwrap() {
-                '0' => {
-                    let b = s.as_bytes();
-                    Self::from_hex(b)
-                }
-                _ => Self::from_decimal(s),
+    /// The string is neither a decimal nor a hex number that fits in 256 bits.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct InvalidU256;
+
+    impl TryFrom<String> for U256 {
+        type Error = InvalidU256;
+
+        /// Besides what `parse` accepts, a multi-digit string starting with
+        /// `0` is read as unprefixed hex, the way `to_hex` writes it.
+        fn try_from(s: String) -> Result<Self, Self::Error> {
+            let parsed = if s.len() > 1 && s.starts_with('0') && !s.starts_with("0x") {
+                Self::from_hex(s.as_bytes())
+            } else {
+                Self::parse(&s)
+            };
+
+            parsed.ok_or(InvalidU256)
+        }
+    }
+}
+
+pub mod psp22 {
+
+    #[cfg(not(test))]
+    use ink_env::call::{build_call, Call, ExecutionInput, Selector};
+    #[cfg(not(test))]
+    use ink_env::{AccountId, DefaultEnvironment};
+    use scale::{Decode, Encode};
+    use scale_info::TypeInfo;
+
+    #[cfg(not(test))]
+    const TRANSFER_SELECTOR: [u8; 4] = [0xdb, 0x20, 0xf9, 0xf5];
+    #[cfg(not(test))]
+    const TRANSFER_FROM_SELECTOR: [u8; 4] = [0x54, 0xb3, 0xc7, 0x6e];
+
+    #[derive(Debug, PartialEq, Eq, TypeInfo, Encode, Decode)]
+    pub enum Psp22Error {
+        Custom(String),
+        InsufficientBalance,
+        InsufficientAllowance,
+        ZeroRecipientAddress,
+        ZeroSenderAddress,
+        SafeTransferCheckFailed(String),
+        CallFailed,
+    }
+
+    #[cfg(not(test))]
+    pub fn transfer(token: AccountId, to: AccountId, value: u128) -> Result<(), Psp22Error> {
+        build_call::<DefaultEnvironment>()
+            .call_type(Call::new().callee(token).gas_limit(0))
+            .exec_input(
+                ExecutionInput::new(Selector::new(TRANSFER_SELECTOR))
+                    .push_arg(to)
+                    .push_arg(value)
+                    .push_arg(Vec::<u8>::new()),
+            )
+            .returns::<Result<(), Psp22Error>>()
+            .fire()
+            .map_err(|_| Psp22Error::CallFailed)?
+    }
+
+    #[cfg(not(test))]
+    pub fn transfer_from(
+        token: AccountId,
+        from: AccountId,
+        to: AccountId,
+        value: u128,
+    ) -> Result<(), Psp22Error> {
+        build_call::<DefaultEnvironment>()
+            .call_type(Call::new().callee(token).gas_limit(0))
+            .exec_input(
+                ExecutionInput::new(Selector::new(TRANSFER_FROM_SELECTOR))
+                    .push_arg(from)
+                    .push_arg(to)
+                    .push_arg(value)
+                    .push_arg(Vec::<u8>::new()),
+            )
+            .returns::<Result<(), Psp22Error>>()
+            .fire()
+            .map_err(|_| Psp22Error::CallFailed)?
+    }
+
+    #[cfg(test)]
+    pub use mock::{transfer, transfer_from};
+
+    /// Stand-in for deployed PSP22 contracts in the off-chain environment,
+    /// which cannot dispatch cross-contract calls.
+    #[cfg(test)]
+    pub mod mock {
+
+        use super::Psp22Error;
+        use ink_env::{AccountId, DefaultEnvironment};
+        use std::cell::RefCell;
+        use std::collections::HashMap;
+
+        thread_local! {
+            static BALANCES: RefCell<HashMap<(AccountId, AccountId), u128>> = RefCell::new(HashMap::new());
+            static ALLOWANCES: RefCell<HashMap<(AccountId, AccountId, AccountId), u128>> = RefCell::new(HashMap::new());
+        }
+
+        pub fn set_balance(token: AccountId, owner: AccountId, value: u128) {
+            BALANCES.with(|b| b.borrow_mut().insert((token, owner), value));
+        }
+
+        pub fn balance_of(token: AccountId, owner: AccountId) -> u128 {
+            BALANCES.with(|b| b.borrow().get(&(token, owner)).copied().unwrap_or_default())
+        }
+
+        pub fn approve(token: AccountId, owner: AccountId, spender: AccountId, value: u128) {
+            ALLOWANCES.with(|a| a.borrow_mut().insert((token, owner, spender), value));
+        }
+
+        pub fn allowance(token: AccountId, owner: AccountId, spender: AccountId) -> u128 {
+            ALLOWANCES.with(|a| {
+                a.borrow()
+                    .get(&(token, owner, spender))
+                    .copied()
+                    .unwrap_or_default()
+            })
+        }
+
+        fn move_balance(
+            token: AccountId,
+            from: AccountId,
+            to: AccountId,
+            value: u128,
+        ) -> Result<(), Psp22Error> {
+            let from_balance = balance_of(token, from);
+
+            if value > from_balance {
+                return Err(Psp22Error::InsufficientBalance);
             }
+
+            set_balance(token, from, from_balance - value);
+            set_balance(token, to, balance_of(token, to) + value);
+
+            Ok(())
+        }
+
+        pub fn transfer(token: AccountId, to: AccountId, value: u128) -> Result<(), Psp22Error> {
+            let caller = ink_env::account_id::<DefaultEnvironment>();
+
+            move_balance(token, caller, to, value)
+        }
+
+        pub fn transfer_from(
+            token: AccountId,
+            from: AccountId,
+            to: AccountId,
+            value: u128,
+        ) -> Result<(), Psp22Error> {
+            let spender = ink_env::account_id::<DefaultEnvironment>();
+            let current = allowance(token, from, spender);
+
+            if value > current {
+                return Err(Psp22Error::InsufficientAllowance);
+            }
+
+            move_balance(token, from, to, value)?;
+            approve(token, from, spender, current - value);
+
+            Ok(())
+        }
+    }
+}
+
+pub mod storage {
+
+    use core::ops::{Deref, DerefMut};
+    use ink_storage::traits::{
+        clear_packed_root, push_packed_root, ExtKeyPtr, KeyPtr, PackedLayout, SpreadAllocate,
+        SpreadLayout,
+    };
+
+    /// A storage field that reads as `T::default()` until it is first written.
+    ///
+    /// A plain root field traps when its cell is empty. Storage written by code
+    /// that predates the field has no such cell, so once that deployment is
+    /// upgraded every message, `migrate` included, would fail to load the
+    /// contract. Fields added to the storage struct after the first release
+    /// are therefore wrapped in this.
+    #[derive(Debug, Default)]
+    pub struct Defaulted<T>(T);
+
+    impl<T> Deref for Defaulted<T> {
+        type Target = T;
+
+        fn deref(&self) -> &T {
+            &self.0
+        }
+    }
+
+    impl<T> DerefMut for Defaulted<T> {
+        fn deref_mut(&mut self) -> &mut T {
+            &mut self.0
+        }
+    }
+
+    impl<T: PackedLayout + Default> SpreadLayout for Defaulted<T> {
+        const FOOTPRINT: u64 = 1;
+        const REQUIRES_DEEP_CLEAN_UP: bool = <T as SpreadLayout>::REQUIRES_DEEP_CLEAN_UP;
+
+        fn pull_spread(ptr: &mut KeyPtr) -> Self {
+            let key = ptr.next_for::<Self>();
+            let value = ink_env::get_contract_storage::<T>(key)
+                .unwrap_or_else(|error| {
+                    panic!("failed to pull packed from root key {}: {:?}", key, error)
+                })
+                .map(|mut value| {
+                    <T as PackedLayout>::pull_packed(&mut value, key);
+                    value
+                })
+                .unwrap_or_default();
+
+            Self(value)
+        }
+
+        fn push_spread(&self, ptr: &mut KeyPtr) {
+            push_packed_root(&self.0, ptr.next_for::<Self>());
+        }
+
+        fn clear_spread(&self, ptr: &mut KeyPtr) {
+            clear_packed_root(&self.0, ptr.next_for::<Self>());
+        }
+    }
+
+    impl<T: PackedLayout + Default> SpreadAllocate for Defaulted<T> {
+        fn allocate_spread(ptr: &mut KeyPtr) -> Self {
+            ptr.next_for::<Self>();
+
+            Self::default()
+        }
+    }
+
+    #[cfg(feature = "std")]
+    impl<T> ink_storage::traits::StorageLayout for Defaulted<T>
+    where
+        T: PackedLayout + Default + scale_info::TypeInfo + 'static,
+    {
+        fn layout(key_ptr: &mut KeyPtr) -> ink_metadata::layout::Layout {
+            use ink_metadata::layout::{CellLayout, Layout, LayoutKey};
+
+            Layout::Cell(CellLayout::new::<T>(LayoutKey::from(key_ptr.advance_by(1))))
         }
     }
 }
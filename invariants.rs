@@ -0,0 +1,52 @@
+pub mod ledger {
+
+    use crate::bridge_cherry_contract::Bridge;
+    use crate::helpers::utils::{MultiChainAddrHash, U256};
+    use ink_env::{AccountId, DefaultEnvironment};
+    use std::cell::RefCell;
+    use std::collections::{BTreeMap, BTreeSet};
+
+    /// Holders seen per contract and token, so bridges living side by side in
+    /// one test only check their own ledger.
+    type Holders = BTreeMap<(AccountId, MultiChainAddrHash), BTreeSet<MultiChainAddrHash>>;
+
+    thread_local! {
+        static HOLDERS: RefCell<Holders> = const { RefCell::new(BTreeMap::new()) };
+    }
+
+    fn callee() -> AccountId {
+        ink_env::account_id::<DefaultEnvironment>()
+    }
+
+    pub fn record_holder(token: &MultiChainAddrHash, owner: &MultiChainAddrHash) {
+        HOLDERS.with(|h| {
+            h.borrow_mut()
+                .entry((callee(), token.clone()))
+                .or_default()
+                .insert(owner.clone());
+        });
+    }
+
+    pub fn assert_supply_matches(bridge: &Bridge) {
+        let callee = callee();
+
+        HOLDERS.with(|h| {
+            for ((contract, token), holders) in h.borrow().iter() {
+                if *contract != callee {
+                    continue;
+                }
+
+                let sum = holders.iter().fold(U256::new_zero(), |sum, holder| {
+                    U256::add_a_with_b(&sum, &bridge.balance_of(holder.clone(), token.clone()))
+                });
+
+                assert_eq!(
+                    sum,
+                    bridge.total_supply(token.clone()),
+                    "sum of balances differs from total supply for token {}",
+                    token
+                );
+            }
+        });
+    }
+}
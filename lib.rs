@@ -1,16 +1,35 @@
 #![cfg_attr(not(feature = "std"), no_std)]
+#![allow(clippy::let_unit_value, clippy::large_enum_variant)]
 
-mod helpers;
+pub mod helpers;
+#[cfg(test)]
+mod invariants;
 
 use ink_lang as ink;
 
 #[ink::contract]
-mod bridge_cherry_contract {
-    const CHER_TOKEN: &'static str = "";
+pub mod bridge_cherry_contract {
+    const NATIVE_TOKEN: &str = "NATIVE";
+    const DEFAULT_OUTBOUND_TTL: Timestamp = 7 * 24 * 60 * 60 * 1000;
+    const MAX_FEE_BPS: u32 = 10_000;
+    const MAX_PAGE_SIZE: u32 = 100;
+    const PERMIT_DOMAIN: &[u8] = b"CHERRY_BRIDGE_PERMIT_V1";
+    const BRIDGE_OUT_DOMAIN: &[u8] = b"CHERRY_BRIDGE_OUT_V1";
+    const DEFAULT_UPGRADE_DELAY: Timestamp = 2 * 24 * 60 * 60 * 1000;
 
-    use crate::helpers::utils::{MultiChainAddrHash, U256};
+    /// Layout version of the storage written by this code. Bumped, together
+    /// with a new step in `migrate_from`, whenever a release changes how
+    /// existing storage is laid out.
+    const STORAGE_VERSION: u32 = 1;
+
+    use crate::helpers::psp22::{self, Psp22Error};
+    use crate::helpers::storage::Defaulted;
+    use crate::helpers::utils::{InvalidU256, MultiChainAddrHash, U256};
     use ink_storage::Mapping;
 
+    /// Fields are laid out in declaration order, so new fields are only ever
+    /// appended. Every field added after the first release is a `Mapping` or
+    /// a `Defaulted`, which both load from storage that lacks them.
     #[ink(storage)]
     #[derive(ink_storage::traits::SpreadAllocate)]
     pub struct Bridge {
@@ -18,34 +37,137 @@ mod bridge_cherry_contract {
         total_supply: Mapping<MultiChainAddrHash, U256>,
         balances: Mapping<(MultiChainAddrHash, MultiChainAddrHash), U256>,
         allowances: Mapping<(MultiChainAddrHash, MultiChainAddrHash, MultiChainAddrHash), U256>,
+        locked_balances: Mapping<MultiChainAddrHash, U256>,
+        psp22_tokens: Mapping<MultiChainAddrHash, AccountId>,
+        min_native_amount: Defaulted<Balance>,
+        operators: Mapping<(MultiChainAddrHash, MultiChainAddrHash), bool>,
+        relayers: Mapping<AccountId, bool>,
+        outbound_messages: Mapping<u64, OutboundMessage>,
+        next_outbound_nonce: Defaulted<u64>,
+        outbound_ttl: Defaulted<Timestamp>,
+        refund_fee_bps: Defaulted<u32>,
+        relay_fee_bps: Defaulted<u32>,
+        paused: Defaulted<bool>,
+        token_holders: Mapping<(MultiChainAddrHash, u32), MultiChainAddrHash>,
+        token_holder_count: Mapping<MultiChainAddrHash, u32>,
+        tokens: Mapping<u32, MultiChainAddrHash>,
+        token_count: Defaulted<u32>,
+        owner_allowances:
+            Mapping<(MultiChainAddrHash, u32), (MultiChainAddrHash, MultiChainAddrHash)>,
+        owner_allowance_count: Mapping<MultiChainAddrHash, u32>,
+        token_metadata: Mapping<MultiChainAddrHash, TokenMetadata>,
+        cherry_token: Defaulted<MultiChainAddrHash>,
+        chains: Mapping<U256, ChainConfig>,
+        collected_fees: Mapping<MultiChainAddrHash, U256>,
+        cherry_attesters: Mapping<AccountId, bool>,
+        processed_inbound: Mapping<(U256, u64), bool>,
+        permit_nonces: Mapping<AccountId, u64>,
+        legacy_events: Defaulted<bool>,
+        storage_version: Defaulted<u32>,
+        upgrade_delay: Defaulted<Timestamp>,
+        pending_upgrade: Defaulted<Option<PendingUpgrade>>,
+        allowance_owners: Mapping<u32, MultiChainAddrHash>,
+        allowance_owner_count: Defaulted<u32>,
+        processed_inbound_index: Mapping<u32, (U256, u64)>,
+        processed_inbound_count: Defaulted<u32>,
+        state_imported: Defaulted<bool>,
+        import_checksum: Defaulted<[u8; 32]>,
+        imported_chunks: Defaulted<u32>,
+        expected_import_checksum: Defaulted<Option<[u8; 32]>>,
     }
 
     #[ink(event)]
-    pub struct BridgeCherryComplex {
-        #[ink(topic)]
-        token_amount: U256,
+    pub struct ChainConfigured {
         #[ink(topic)]
-        recipient: MultiChainAddrHash,
+        chain_id: U256,
+        config: Option<ChainConfig>,
     }
 
     #[ink(event)]
-    pub struct BridgeCherrySimple {
-        token_amount: String,
-        recipient: String,
+    pub struct CherryTokenUpdated {
+        #[ink(topic)]
+        old_token: MultiChainAddrHash,
+        #[ink(topic)]
+        new_token: MultiChainAddrHash,
+    }
+
+    /// Version of the `BridgedOut`/`BridgedIn` schema, carried in every such
+    /// event. Bumped whenever a field is added, removed or reinterpreted.
+    ///
+    /// Off-chain decoders identify events by their declaration order in this
+    /// module, so new events must only ever be appended after the last one.
+    const EVENT_SCHEMA_VERSION: u8 = 1;
+
+    /// How a bridged amount is held on this side of the bridge.
+    #[derive(
+        scale::Encode, scale::Decode, scale_info::TypeInfo, Debug, Clone, Copy, PartialEq, Eq,
+    )]
+    pub enum BridgeRoute {
+        /// A ledger or PSP22 token, locked in custody.
+        Token,
+        /// The chain's native currency, locked in the contract balance.
+        Native,
+        /// Cherry, locked in custody under its chain's rules.
+        CherryLock,
+        /// Cherry, burned on the way out and minted on the way in.
+        CherryBurn,
     }
 
+    /// Emitted once for every outbound transfer, whichever message started it.
+    ///
+    /// The topic fields carry the blake2-256 digests of `token_address` and
+    /// `sender_address` and the target chain id, so indexers can filter
+    /// without decoding. `amount` is what was locked or burned, after `fee`.
     #[ink(event)]
-    pub struct BridgeInComplex {
+    pub struct BridgedOut {
+        version: u8,
         #[ink(topic)]
+        token: [u8; 32],
+        #[ink(topic)]
+        sender: [u8; 32],
+        #[ink(topic)]
+        target_chain: U256,
+        nonce: u64,
         token_address: MultiChainAddrHash,
+        sender_address: MultiChainAddrHash,
+        recipient: Option<MultiChainAddrHash>,
+        amount: U256,
+        fee: U256,
+        route: BridgeRoute,
+        expires_at: Timestamp,
+    }
+
+    /// Emitted once for every inbound transfer, whichever message delivered it.
+    ///
+    /// The topic fields carry the blake2-256 digests of `token_address` and
+    /// `recipient_address` and the source chain id. `source_nonce` is the
+    /// attested nonce when the route has one; inbound transfers charge no fee
+    /// yet, so `fee` is zero.
+    #[ink(event)]
+    pub struct BridgedIn {
+        version: u8,
         #[ink(topic)]
-        token_amount: U256,
+        token: [u8; 32],
         #[ink(topic)]
-        recipient: MultiChainAddrHash,
+        recipient: [u8; 32],
         #[ink(topic)]
-        from_chain: Option<U256>,
+        source_chain: U256,
+        source_nonce: Option<u64>,
+        token_address: MultiChainAddrHash,
+        recipient_address: MultiChainAddrHash,
+        amount: U256,
+        fee: U256,
+        route: BridgeRoute,
+    }
+
+    /// Legacy string event, only emitted in compatibility mode.
+    #[ink(event)]
+    pub struct BridgeCherrySimple {
+        token_amount: String,
+        recipient: String,
     }
 
+    /// Legacy string event, only emitted in compatibility mode.
     #[ink(event)]
     pub struct BridgeInSimple {
         token_address: String,
@@ -54,24 +176,36 @@ mod bridge_cherry_contract {
         from_chain: Option<String>,
     }
 
+    /// Legacy string event, only emitted in compatibility mode.
+    #[ink(event)]
+    pub struct BridgeOutSimple {
+        token_address: String,
+        token_amount: String,
+        recipient: String,
+        target_chain: Option<String>,
+    }
+
     #[ink(event)]
-    pub struct BridgeOutComplex {
+    pub struct BridgeOutFailed {
         #[ink(topic)]
-        token_address: MultiChainAddrHash,
+        nonce: u64,
+    }
+
+    #[ink(event)]
+    pub struct BridgeOutRefunded {
         #[ink(topic)]
+        nonce: u64,
+        #[ink(topic)]
+        token_address: MultiChainAddrHash,
         token_amount: U256,
+        fee: U256,
         #[ink(topic)]
         recipient: MultiChainAddrHash,
-        #[ink(topic)]
-        target_chain: Option<U256>,
     }
 
     #[ink(event)]
-    pub struct BridgeOutSimple {
-        token_address: String,
-        token_amount: String,
-        recipient: String,
-        target_chain: Option<String>,
+    pub struct PausedChanged {
+        paused: bool,
     }
 
     #[ink(event)]
@@ -81,6 +215,18 @@ mod bridge_cherry_contract {
         initial_balance: String,
     }
 
+    #[ink(event)]
+    pub struct TokenCreated {
+        #[ink(topic)]
+        token: String,
+        by: String,
+        recipient: String,
+        initial_balance: String,
+        decimals: u8,
+        name: String,
+        symbol: String,
+    }
+
     #[ink(event)]
     pub struct Approval {
         #[ink(topic)]
@@ -101,31 +247,433 @@ mod bridge_cherry_contract {
         value_hex: String,
     }
 
-    #[derive(scale::Encode, scale::Decode, scale_info::TypeInfo)]
+    #[ink(event)]
+    pub struct TransferBatch {
+        #[ink(topic)]
+        from: Option<String>,
+        #[ink(topic)]
+        to: Option<String>,
+        tokens: Vec<String>,
+        values_decimal: Vec<String>,
+        values_hex: Vec<String>,
+    }
+
+    #[ink(event)]
+    pub struct ApprovalForAll {
+        #[ink(topic)]
+        owner: String,
+        #[ink(topic)]
+        operator: String,
+        approved: bool,
+    }
+
+    /// Errors returned by `Bridge` messages. Each variant carries a stable
+    /// numeric code, which is also its SCALE index, and must never be reused.
+    #[derive(
+        scale::Encode, scale::Decode, scale_info::TypeInfo, Debug, Clone, Copy, PartialEq, Eq,
+    )]
     pub enum BridgeContractError {
-        ErrorApproving(String),
-        ErrorTransferringFrom(String),
-        ErrorTransferringTo(String),
-        ErrorTransferringFromTo(String),
+        /// The sender's ledger balance is lower than the amount.
+        InsufficientBalance = 1,
+        /// The spender's allowance is lower than the amount.
+        InsufficientAllowance = 2,
+        /// The spender has never been given an allowance.
+        NoAllowance = 3,
+        /// The address cannot be used as a local account.
+        InvalidAddress = 4,
+        /// The amount is zero or does not fit the target balance type.
+        InvalidAmount = 5,
+        /// Bridging is paused by the owner.
+        Paused = 6,
+        /// The caller is not allowed to perform this action.
+        Unauthorized = 7,
+        /// The token is not known to the bridge.
+        UnknownToken = 8,
+        /// The chain is not known to the bridge.
+        UnknownChain = 9,
+        /// Custody does not hold enough of the token to release the amount.
+        InsufficientLockedBalance = 10,
+        /// The amount is below the configured bridge minimum.
+        BelowMinimumAmount = 11,
+        /// The amount is below the chain's existential deposit.
+        BelowExistentialDeposit = 12,
+        /// The transfer would leave the bridge account below the existential deposit.
+        WouldReapAccount = 13,
+        /// The native currency transfer was rejected by the chain.
+        NativeTransferFailed = 14,
+        /// The PSP22 contract reported an insufficient balance.
+        TokenInsufficientBalance = 15,
+        /// The PSP22 contract reported an insufficient allowance.
+        TokenInsufficientAllowance = 16,
+        /// The PSP22 contract call failed for any other reason.
+        TokenCallFailed = 17,
+        /// The fee exceeds 100%.
+        InvalidFee = 18,
+        /// No outbound message exists for the nonce.
+        UnknownOutboundMessage = 19,
+        /// The outbound message has already been attested or refunded.
+        OutboundNotPending = 20,
+        /// The outbound message has not been marked failed.
+        NotRefundable = 21,
+        /// A token with this address already exists.
+        TokenAlreadyExists = 22,
+        /// The inbound message has already been processed.
+        AlreadyProcessed = 23,
+        /// The token does not support the operation required by the chain's rules.
+        UnsupportedOperation = 24,
+        /// The signature does not recover to the expected signer.
+        InvalidSignature = 25,
+        /// The signed request is past its deadline.
+        Expired = 26,
+        /// The fees due exceed the maximum the sender signed for.
+        FeeTooHigh = 27,
+        /// The signed nonce is not the signer's current nonce.
+        InvalidNonce = 28,
+        /// No upgrade to this code hash is scheduled.
+        NoPendingUpgrade = 29,
+        /// The scheduled upgrade's timelock has not elapsed yet.
+        UpgradeTimelocked = 30,
+        /// The chain rejected the new code hash.
+        UpgradeFailed = 31,
+        /// State can only be imported once, into a deployment with an empty ledger.
+        ImportNotAllowed = 32,
+        /// The imported chunks do not hash to the expected checksum.
+        ChecksumMismatch = 33,
+        /// An imported chunk is not a SCALE-encoded `StateChunk`.
+        InvalidStateChunk = 34,
+        /// A string argument is not a decimal or hex number that fits in 256 bits.
+        InvalidNumber = 35,
+        /// The upgrade delay can only be lengthened.
+        DelayTooShort = 36,
+        /// The upgrade delay puts the upgrade past the largest timestamp.
+        DelayOverflow = 37,
+        /// A state import is open and the ledger is only partly loaded.
+        ImportInProgress = 38,
+    }
+
+    impl BridgeContractError {
+        pub fn code(&self) -> u16 {
+            *self as u16
+        }
+    }
+
+    #[ink(event)]
+    pub struct BridgeInBatch {
+        total: u32,
+        succeeded: u32,
+        failed: u32,
+    }
+
+    #[ink(event)]
+    pub struct BridgeOutBatch {
+        total: u32,
+        succeeded: u32,
+        failed: u32,
+    }
+
+    #[ink(event)]
+    pub struct UpgradeScheduled {
+        #[ink(topic)]
+        code_hash: Hash,
+        ready_at: Timestamp,
+    }
+
+    #[ink(event)]
+    pub struct Upgraded {
+        #[ink(topic)]
+        code_hash: Hash,
+        storage_version: u32,
+    }
+
+    #[ink(event)]
+    pub struct Migrated {
+        from_version: u32,
+        to_version: u32,
+    }
+
+    #[ink(event)]
+    pub struct StateImported {
+        chunks: u32,
+        checksum: [u8; 32],
+    }
+
+    #[derive(
+        scale::Encode,
+        scale::Decode,
+        scale_info::TypeInfo,
+        ink_storage::traits::PackedLayout,
+        ink_storage::traits::SpreadLayout,
+        ink_storage::traits::StorageLayout,
+        Debug,
+        Clone,
+        Copy,
+        PartialEq,
+        Eq,
+    )]
+    pub enum OutboundStatus {
+        Pending,
+        Delivered,
+        Failed,
+        Refunded,
+    }
+
+    #[derive(
+        scale::Encode,
+        scale::Decode,
+        scale_info::TypeInfo,
+        ink_storage::traits::PackedLayout,
+        ink_storage::traits::SpreadLayout,
+        ink_storage::traits::StorageLayout,
+        Debug,
+        Clone,
+    )]
+    pub struct OutboundMessage {
+        pub sender: MultiChainAddrHash,
+        pub token_address: MultiChainAddrHash,
+        pub token_amount: U256,
+        pub target_chain: U256,
+        /// When relayers stop delivering the message and mark it failed
+        /// instead, which is what makes it refundable.
+        pub expires_at: Timestamp,
+        pub status: OutboundStatus,
+        pub burned: bool,
+    }
+
+    #[derive(
+        scale::Encode,
+        scale::Decode,
+        scale_info::TypeInfo,
+        ink_storage::traits::PackedLayout,
+        ink_storage::traits::SpreadLayout,
+        ink_storage::traits::StorageLayout,
+        Debug,
+        Clone,
+        Copy,
+        PartialEq,
+        Eq,
+    )]
+    pub enum CherryMode {
+        Lock,
+        Burn,
+    }
+
+    #[derive(
+        scale::Encode,
+        scale::Decode,
+        scale_info::TypeInfo,
+        ink_storage::traits::PackedLayout,
+        ink_storage::traits::SpreadLayout,
+        ink_storage::traits::StorageLayout,
+        Debug,
+        Clone,
+        Copy,
+        PartialEq,
+        Eq,
+    )]
+    pub struct ChainConfig {
+        pub fee_bps: u32,
+        pub cherry_fee_bps: u32,
+        pub cherry_mode: CherryMode,
+    }
+
+    /// A code upgrade waiting for its timelock to elapse.
+    #[derive(
+        scale::Encode,
+        scale::Decode,
+        scale_info::TypeInfo,
+        ink_storage::traits::PackedLayout,
+        ink_storage::traits::SpreadLayout,
+        ink_storage::traits::StorageLayout,
+        Debug,
+        Clone,
+        Copy,
+        PartialEq,
+        Eq,
+    )]
+    pub struct PendingUpgrade {
+        pub code_hash: Hash,
+        pub ready_at: Timestamp,
+    }
+
+    #[derive(
+        scale::Encode,
+        scale::Decode,
+        scale_info::TypeInfo,
+        ink_storage::traits::PackedLayout,
+        ink_storage::traits::SpreadLayout,
+        ink_storage::traits::StorageLayout,
+        Debug,
+        Default,
+        Clone,
+        PartialEq,
+        Eq,
+    )]
+    pub struct TokenMetadata {
+        pub name: String,
+        pub symbol: String,
+        pub decimals: u8,
+    }
+
+    #[derive(scale::Encode, scale::Decode, scale_info::TypeInfo, Debug, Clone)]
+    pub struct TokenAllocation {
+        pub token: MultiChainAddrHash,
+        pub supply: U256,
+        pub recipient: MultiChainAddrHash,
+        pub metadata: TokenMetadata,
+    }
+
+    #[derive(scale::Encode, scale::Decode, scale_info::TypeInfo, Debug, Clone, PartialEq, Eq)]
+    pub struct TokenAudit {
+        pub total_supply: U256,
+        pub sum_of_balances: U256,
+        pub holders: u32,
+        pub consistent: bool,
+    }
+
+    #[derive(scale::Encode, scale::Decode, scale_info::TypeInfo, Clone, Copy, PartialEq, Eq)]
+    pub enum BatchMode {
+        AllOrNothing,
+        BestEffort,
+    }
+
+    #[derive(scale::Encode, scale::Decode, scale_info::TypeInfo, Clone)]
+    pub struct InboundMessage {
+        pub token_address: MultiChainAddrHash,
+        pub token_amount: U256,
+        pub recipient: MultiChainAddrHash,
+        pub from_chain: U256,
+        pub source_nonce: u64,
+    }
+
+    #[derive(scale::Encode, scale::Decode, scale_info::TypeInfo, Clone)]
+    pub struct OutboundRequest {
+        pub token_address: MultiChainAddrHash,
+        pub token_amount: U256,
+        pub target_chain: U256,
+    }
+
+    /// An allowance signed by `owner`, as accepted by `bridge_out_with_permit`.
+    #[derive(scale::Encode, scale::Decode, scale_info::TypeInfo, Clone)]
+    pub struct Permit {
+        pub owner: AccountId,
+        pub spender: MultiChainAddrHash,
+        pub token: MultiChainAddrHash,
+        pub value: U256,
+        pub deadline: Timestamp,
+    }
+
+    #[derive(scale::Encode, scale::Decode, scale_info::TypeInfo, Clone)]
+    pub struct SignedOutboundRequest {
+        pub signer: AccountId,
+        pub request: OutboundRequest,
+        pub nonce: u64,
+        pub deadline: Timestamp,
+        pub max_fee: U256,
+    }
+
+    /// A part of the bridge state that `export_state` pages through.
+    #[derive(scale::Encode, scale::Decode, scale_info::TypeInfo, Debug, Clone, PartialEq, Eq)]
+    pub enum StateSection {
+        /// Every token, in creation order.
+        Tokens,
+        /// The holders of a token and their balances.
+        Balances(MultiChainAddrHash),
+        /// The allowances given by an owner.
+        Allowances(MultiChainAddrHash),
+        /// Outbound messages, by nonce.
+        Outbound,
+        /// Processed inbound source nonces.
+        Inbound,
+    }
+
+    #[derive(scale::Encode, scale::Decode, scale_info::TypeInfo, Debug, Clone, PartialEq, Eq)]
+    pub struct TokenState {
+        pub token: MultiChainAddrHash,
+        pub metadata: TokenMetadata,
+        pub total_supply: U256,
+        pub locked: Option<U256>,
+        pub psp22_contract: Option<AccountId>,
+        pub collected_fees: U256,
+    }
+
+    /// One page of exported state, as SCALE-encoded by `export_state` and
+    /// decoded by `import_state`.
+    #[derive(scale::Encode, scale::Decode, scale_info::TypeInfo, Debug, Clone)]
+    pub enum StateChunk {
+        Tokens(Vec<TokenState>),
+        Balances {
+            token: MultiChainAddrHash,
+            balances: Vec<(MultiChainAddrHash, U256)>,
+        },
+        Allowances {
+            owner: MultiChainAddrHash,
+            allowances: Vec<(MultiChainAddrHash, MultiChainAddrHash, U256)>,
+        },
+        Outbound {
+            next_nonce: u64,
+            messages: Vec<(u64, OutboundMessage)>,
+        },
+        Inbound(Vec<(U256, u64)>),
+    }
+
+    impl From<InvalidU256> for BridgeContractError {
+        fn from(_: InvalidU256) -> Self {
+            BridgeContractError::InvalidNumber
+        }
+    }
+
+    impl From<Psp22Error> for BridgeContractError {
+        fn from(e: Psp22Error) -> Self {
+            match e {
+                Psp22Error::InsufficientBalance => BridgeContractError::TokenInsufficientBalance,
+                Psp22Error::InsufficientAllowance => {
+                    BridgeContractError::TokenInsufficientAllowance
+                }
+                _ => BridgeContractError::TokenCallFailed,
+            }
+        }
     }
 
     impl Bridge {
         #[ink(constructor)]
-        pub fn new(initial_token: MultiChainAddrHash, initial_supply: U256) -> Self {
+        pub fn new(
+            initial_token: MultiChainAddrHash,
+            initial_supply: U256,
+            cherry_token: MultiChainAddrHash,
+        ) -> Self {
+            ink_lang::utils::initialize_contract(|contract| {
+                Self::new_init(contract, initial_token, initial_supply, cherry_token)
+            })
+        }
+
+        #[ink(constructor)]
+        pub fn new_with_allocations(
+            allocations: Vec<TokenAllocation>,
+            cherry_token: MultiChainAddrHash,
+        ) -> Self {
             ink_lang::utils::initialize_contract(|contract| {
-                Self::new_init(contract, initial_token, initial_supply)
+                Self::new_with_allocations_init(contract, allocations, cherry_token)
             })
         }
 
-        fn new_init(&mut self, initial_token: MultiChainAddrHash, initial_supply: U256) {
+        fn new_init(
+            &mut self,
+            initial_token: MultiChainAddrHash,
+            initial_supply: U256,
+            cherry_token: MultiChainAddrHash,
+        ) {
             let caller = self.env().caller();
             let caller_arr: &[u8] = caller.as_ref();
 
             let addr_multi: MultiChainAddrHash = caller_arr.into();
 
-            self.balances
-                .insert((&addr_multi, &initial_token), &initial_supply);
-            self.total_supply.insert(initial_token, &initial_supply);
+            self.init_config(caller, cherry_token);
+            self.allocate(TokenAllocation {
+                token: initial_token,
+                supply: initial_supply,
+                recipient: addr_multi.clone(),
+                metadata: TokenMetadata::default(),
+            });
 
             Self::env().emit_event(Initiate {
                 initiated: true,
@@ -134,286 +682,4391 @@ mod bridge_cherry_contract {
             });
         }
 
-        #[ink(message)]
-        pub fn get_balance_of(&self, token: String, owner: String) -> Option<U256> {
-            let mcah: MultiChainAddrHash = owner.into();
-            let tcah: MultiChainAddrHash = token.into();
+        fn new_with_allocations_init(
+            &mut self,
+            allocations: Vec<TokenAllocation>,
+            cherry_token: MultiChainAddrHash,
+        ) {
+            self.init_config(self.env().caller(), cherry_token);
 
-            self.balances.get((mcah, tcah))
+            for allocation in allocations {
+                self.allocate(allocation);
+            }
         }
 
-        #[ink(message)]
-        pub fn get_allowance_of(
-            &self,
-            owner: String,
-            spender: String,
-            token: String,
-        ) -> Option<U256> {
-            let mcah_owner: MultiChainAddrHash = owner.into();
-            let mcah_spender: MultiChainAddrHash = spender.into();
-            let tcah: MultiChainAddrHash = token.into();
+        fn init_config(&mut self, owner: AccountId, cherry_token: MultiChainAddrHash) {
+            self.owner = owner;
+            *self.cherry_token = cherry_token;
+            *self.outbound_ttl = DEFAULT_OUTBOUND_TTL;
+            *self.storage_version = STORAGE_VERSION;
+            *self.upgrade_delay = DEFAULT_UPGRADE_DELAY;
+        }
 
-            self.allowances.get((mcah_owner, mcah_spender, tcah))
+        fn allocate(&mut self, allocation: TokenAllocation) {
+            let TokenAllocation {
+                token,
+                supply,
+                recipient,
+                metadata,
+            } = allocation;
+
+            if !self.total_supply.contains(&token) {
+                self.token_metadata.insert(&token, &metadata);
+
+                Self::env().emit_event(TokenCreated {
+                    token: token.to_string(),
+                    by: self.caller_hash().to_string(),
+                    recipient: recipient.to_string(),
+                    initial_balance: supply.to_decimal(),
+                    decimals: metadata.decimals,
+                    name: metadata.name,
+                    symbol: metadata.symbol,
+                });
+            }
+
+            self.mint(&recipient, &token, &supply);
         }
 
-        fn transfer_from_to(
+        fn mint(&mut self, to: &MultiChainAddrHash, token: &MultiChainAddrHash, value: &U256) {
+            let balance = self.balance_of(to.clone(), token.clone());
+            let supply_total = self.total_supply(token.clone());
+
+            self.set_balance(to, token, &U256::add_a_with_b(&balance, value));
+            self.total_supply
+                .insert(token, &U256::add_a_with_b(&supply_total, value));
+            self.ledger_changed();
+        }
+
+        fn burn(
             &mut self,
             from: &MultiChainAddrHash,
-            to: &MultiChainAddrHash,
             token: &MultiChainAddrHash,
             value: &U256,
         ) -> Result<(), BridgeContractError> {
-            let from_balance = self
-                .get_balance_of(token.to_string(), from.clone().to_string())
-                .unwrap();
+            let balance = self.balance_of(from.clone(), token.clone());
 
-            if U256::a_greater_than_b(&value, &from_balance) {
-                return Err(BridgeContractError::ErrorTransferringFromTo(
-                    "Not enough funds".to_string(),
-                ));
+            if U256::a_greater_than_b(value, &balance) {
+                return Err(BridgeContractError::InsufficientBalance);
             }
 
-            let sub_from = U256::subtract_b_from_a(&from_balance, &value);
-
-            self.balances.insert((from, token), &sub_from);
+            let supply_total = self.total_supply(token.clone());
 
-            let to_balance = self
-                .get_balance_of(to.to_string(), token.to_string())
-                .unwrap();
-
-            let sub_to = U256::subtract_b_from_a(&to_balance, &value);
-
-            self.balances.insert((to, token), &sub_to);
-
-            Self::env().emit_event(Transfer {
-                from: Some(from.to_string()),
-                to: Some(to.to_string()),
-                value_decimal: value.to_decimal(),
-                value_hex: value.to_hex(),
-            });
+            self.set_balance(from, token, &U256::subtract_b_from_a(&balance, value));
+            self.total_supply
+                .insert(token, &U256::subtract_b_from_a(&supply_total, value));
+            self.ledger_changed();
 
             Ok(())
         }
 
-        pub fn transfer_from(
+        #[ink(message)]
+        pub fn create_token(
             &mut self,
-            from: &MultiChainAddrHash,
-            to: &MultiChainAddrHash,
-            token: &MultiChainAddrHash,
-            value: &U256,
+            allocation: TokenAllocation,
         ) -> Result<(), BridgeContractError> {
-            let caller = self.env().caller();
+            self.ensure_owner()?;
+            self.ensure_not_importing()?;
 
-            let caller_arr: &[u8] = caller.as_ref();
+            if self.total_supply.contains(&allocation.token) {
+                return Err(BridgeContractError::TokenAlreadyExists);
+            }
 
-            let owner: MultiChainAddrHash = caller_arr.into();
+            self.allocate(allocation);
 
-            let allowance =
-                self.get_allowance_of(from.to_string(), owner.to_string(), token.to_string());
+            Ok(())
+        }
 
-            match allowance {
-                Some(all) => {
-                    if U256::a_greater_than_b(value, &all) {
-                        return Err(BridgeContractError::ErrorTransferringFrom(
-                            "Insufficient Allowance".to_string(),
-                        ));
-                    }
+        #[ink(message)]
+        pub fn token_metadata(&self, token: MultiChainAddrHash) -> Option<TokenMetadata> {
+            self.token_metadata.get(token)
+        }
 
-                    self.transfer_from_to(from, to, token, value)?;
+        #[ink(message)]
+        pub fn get_balance_of(&self, token: String, owner: String) -> U256 {
+            let mcah = MultiChainAddrHash::from(owner);
+            let tcah = MultiChainAddrHash::from(token);
 
-                    let sub = U256::subtract_b_from_a(&all, value);
+            self.balance_of(mcah, tcah)
+        }
 
-                    self.allowances.insert((from, to, token), &sub);
-                }
-                None => {
-                    return Err(BridgeContractError::ErrorTransferringFrom(
-                        "No such allowance".to_string(),
-                    ))
-                }
-            }
+        #[ink(message)]
+        pub fn get_allowance_of(
+            &self,
+            owner: String,
+            spender: String,
+            token: String,
+        ) -> Option<U256> {
+            let mcah_owner = MultiChainAddrHash::from(owner);
+            let mcah_spender = MultiChainAddrHash::from(spender);
+            let tcah = MultiChainAddrHash::from(token);
 
-            Ok(())
+            self.allowances.get((mcah_owner, mcah_spender, tcah))
         }
 
-        pub fn transfer(
+        #[ink(message)]
+        pub fn locked_balance(&self, token: MultiChainAddrHash) -> U256 {
+            self.locked_balances.get(token).unwrap_or_default()
+        }
+
+        #[ink(message)]
+        pub fn register_psp22_token(
             &mut self,
-            to: &MultiChainAddrHash,
-            token: &MultiChainAddrHash,
-            value: &U256,
+            token: MultiChainAddrHash,
+            contract: AccountId,
         ) -> Result<(), BridgeContractError> {
-            let owner = self.env().caller();
-            let owner_ref: &[u8] = owner.as_ref();
+            self.ensure_owner()?;
+            self.ensure_not_importing()?;
 
-            let ohac: MultiChainAddrHash = owner_ref.into();
+            self.psp22_tokens.insert(&token, &contract);
+            self.index_token(&token);
 
-            self.transfer_from_to(&ohac, to, token, value)
+            Ok(())
         }
 
         #[ink(message)]
-        pub fn approve(
-            &mut self,
-            spender: MultiChainAddrHash,
-            token: MultiChainAddrHash,
-            value: U256,
-        ) {
-            let owner = self.env().caller();
+        pub fn psp22_contract_of(&self, token: MultiChainAddrHash) -> Option<AccountId> {
+            self.psp22_tokens.get(token)
+        }
 
-            let caller_arr: &[u8] = owner.as_ref();
+        /// The contract account, addressed the same way as any other account.
+        fn custody(&self) -> MultiChainAddrHash {
+            let contract = self.env().account_id();
 
-            let owner: MultiChainAddrHash = caller_arr.into();
+            (contract.as_ref() as &[u8]).into()
+        }
 
-            self.allowances.insert((&owner, &spender, &token), &value);
-            self.env().emit_event(Approval {
-                owner: owner.to_string(),
-                spender: spender.to_string(),
-                value_decimal: value.to_decimal(),
-                value_hex: value.to_hex(),
-            });
+        fn lock(&mut self, token: &MultiChainAddrHash, value: &U256) {
+            let locked = self.locked_balance(token.clone());
+            let add_locked = U256::add_a_with_b(&locked, value);
+
+            self.locked_balances.insert(token, &add_locked);
+            self.index_token(token);
         }
 
-        #[ink(message)]
-        pub fn bridge_cherry(
-            &mut self,
-            token_amount: U256,
-            recipient: MultiChainAddrHash,
-        ) -> Result<(), BridgeContractError> {
-            let token: MultiChainAddrHash = CHER_TOKEN.clone().to_string().into();
+        fn ensure_owner(&self) -> Result<(), BridgeContractError> {
+            if self.env().caller() != self.owner {
+                return Err(BridgeContractError::Unauthorized);
+            }
 
-            self.transfer(&recipient, &token, &token_amount)?;
+            Ok(())
+        }
 
-            Self::env().emit_event(BridgeCherryComplex {
-                token_amount,
-                recipient,
-            });
+        fn ensure_not_paused(&self) -> Result<(), BridgeContractError> {
+            if *self.paused {
+                return Err(BridgeContractError::Paused);
+            }
+
+            self.ensure_not_importing()
+        }
+
+        /// Between `begin_import` and a successful `finalize_import` the ledger
+        /// is only partly loaded, so no message may change it.
+        fn ensure_not_importing(&self) -> Result<(), BridgeContractError> {
+            if self.expected_import_checksum.is_some() && !*self.state_imported {
+                return Err(BridgeContractError::ImportInProgress);
+            }
 
             Ok(())
         }
 
-        #[ink(message)]
-        pub fn bridge_cherry_string(
-            &mut self,
-            token_amount_str: String,
-            recipient_str: String,
-            emit_simple: bool,
-        ) -> Result<(), BridgeContractError> {
-            let token_amount = token_amount_str.clone().into();
-            let recipient = recipient_str.clone().into();
+        fn ensure_nonzero(value: &U256) -> Result<(), BridgeContractError> {
+            if *value == U256::new_zero() {
+                return Err(BridgeContractError::InvalidAmount);
+            }
 
-            self.bridge_cherry(token_amount, recipient)?;
+            Ok(())
+        }
 
-            if emit_simple {
-                Self::env().emit_event(BridgeCherrySimple {
-                    token_amount: token_amount_str,
-                    recipient: recipient_str,
-                });
+        fn ensure_relayer(&self) -> Result<(), BridgeContractError> {
+            if !self.is_relayer(self.env().caller()) {
+                return Err(BridgeContractError::Unauthorized);
             }
 
             Ok(())
         }
 
-        #[ink(message)]
-        pub fn bridge_in(
+        fn payout(
             &mut self,
-            token_address: MultiChainAddrHash,
-            token_amount: U256,
-            recipient: MultiChainAddrHash,
-            from_chain: U256,
+            token: &MultiChainAddrHash,
+            to: &MultiChainAddrHash,
+            value: &U256,
         ) -> Result<(), BridgeContractError> {
-            self.transfer(&recipient, &token_address, &token_amount)?;
+            if U256::a_greater_than_b(value, &self.locked_balance(token.clone())) {
+                return Err(BridgeContractError::InsufficientLockedBalance);
+            }
 
-            Self::env().emit_event(BridgeInComplex {
-                token_address,
-                token_amount,
-                recipient,
-                from_chain: Some(from_chain),
-            });
+            self.send_from_custody(token, to, value)?;
+            self.unlock(token, value)
+        }
+
+        /// Stops counting `value` of `token` as locked, leaving it in custody.
+        fn unlock(
+            &mut self,
+            token: &MultiChainAddrHash,
+            value: &U256,
+        ) -> Result<(), BridgeContractError> {
+            let locked = self.locked_balance(token.clone());
+
+            if U256::a_greater_than_b(value, &locked) {
+                return Err(BridgeContractError::InsufficientLockedBalance);
+            }
+
+            let sub_locked = U256::subtract_b_from_a(&locked, value);
+
+            self.locked_balances.insert(token, &sub_locked);
+
+            Ok(())
+        }
+
+        fn send_from_custody(
+            &mut self,
+            token: &MultiChainAddrHash,
+            to: &MultiChainAddrHash,
+            value: &U256,
+        ) -> Result<(), BridgeContractError> {
+            if *token == MultiChainAddrHash::from(NATIVE_TOKEN.to_string()) {
+                self.native_transfer(to, value)?;
+            } else {
+                match self.psp22_tokens.get(token) {
+                    Some(contract) => {
+                        let recipient = Self::local_account(to)?;
+                        let amount = value.to_u128().ok_or(BridgeContractError::InvalidAmount)?;
+
+                        psp22::transfer(contract, recipient, amount)?;
+                    }
+                    None => {
+                        let custody = self.custody();
+
+                        self.transfer_from_to(&custody, to, token, value)?;
+                    }
+                }
+            }
+
+            Ok(())
+        }
+
+        fn pull_into_custody(
+            &mut self,
+            from: AccountId,
+            token: &MultiChainAddrHash,
+            value: &U256,
+        ) -> Result<(), BridgeContractError> {
+            // A zero-fee burn has nothing to pull, and must not need an
+            // allowance for it.
+            if *value == U256::new_zero() {
+                return Ok(());
+            }
+
+            match self.psp22_tokens.get(token) {
+                Some(contract) => {
+                    let amount = value.to_u128().ok_or(BridgeContractError::InvalidAmount)?;
+
+                    psp22::transfer_from(contract, from, self.env().account_id(), amount)?;
+                }
+                None => {
+                    let custody = self.custody();
+                    let from: MultiChainAddrHash = (from.as_ref() as &[u8]).into();
+
+                    self.transfer_from_as(&custody, &from, &custody, token, value)?
+                }
+            }
 
             Ok(())
         }
 
+        fn split_fee(value: &U256, fee_bps: u32) -> Result<(U256, U256), BridgeContractError> {
+            let fee = value
+                .apply_bps(fee_bps)
+                .ok_or(BridgeContractError::InvalidAmount)?;
+
+            Ok((U256::subtract_b_from_a(value, &fee), fee))
+        }
+
+        fn collect_fee(&mut self, token: &MultiChainAddrHash, fee: &U256) {
+            let collected = self.collected_fees(token.clone());
+
+            self.collected_fees
+                .insert(token, &U256::add_a_with_b(&collected, fee));
+        }
+
         #[ink(message)]
-        pub fn bridge_in_string(
+        pub fn collected_fees(&self, token: MultiChainAddrHash) -> U256 {
+            self.collected_fees.get(token).unwrap_or_default()
+        }
+
+        #[ink(message)]
+        pub fn withdraw_fees(
             &mut self,
-            token_address_str: String,
-            token_amount_str: String,
-            recipient_str: String,
-            from_chain_str: String,
-            emit_simple: bool,
+            token: MultiChainAddrHash,
+            to: MultiChainAddrHash,
+            value: U256,
         ) -> Result<(), BridgeContractError> {
-            let token_address = token_address_str.clone().into();
-            let token_amount = token_amount_str.clone().into();
-            let recipient: MultiChainAddrHash = recipient_str.clone().into();
-            let from_chain = from_chain_str.clone().into();
+            self.ensure_owner()?;
+            self.ensure_not_importing()?;
 
-            self.bridge_in(token_address, token_amount, recipient, from_chain)?;
+            let collected = self.collected_fees(token.clone());
 
-            let caller = self.env().caller();
-            let token_address: MultiChainAddrHash = (caller.as_ref() as &[u8]).into();
+            if U256::a_greater_than_b(&value, &collected) {
+                return Err(BridgeContractError::InsufficientLockedBalance);
+            }
+
+            self.send_from_custody(&token, &to, &value)?;
+            self.collected_fees
+                .insert(&token, &U256::subtract_b_from_a(&collected, &value));
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn chain(&self, chain_id: U256) -> Option<ChainConfig> {
+            self.chains.get(chain_id)
+        }
+
+        #[ink(message)]
+        pub fn set_chain(
+            &mut self,
+            chain_id: U256,
+            config: Option<ChainConfig>,
+        ) -> Result<(), BridgeContractError> {
+            self.ensure_owner()?;
+
+            match config {
+                Some(c) => {
+                    if c.fee_bps > MAX_FEE_BPS || c.cherry_fee_bps > c.fee_bps {
+                        return Err(BridgeContractError::InvalidFee);
+                    }
+
+                    self.chains.insert(chain_id, &c);
+                }
+                None => self.chains.remove(chain_id),
+            }
+
+            Self::env().emit_event(ChainConfigured { chain_id, config });
+
+            Ok(())
+        }
+
+        fn native_transfer(
+            &mut self,
+            to: &MultiChainAddrHash,
+            value: &U256,
+        ) -> Result<(), BridgeContractError> {
+            let to = Self::local_account(to)?;
+            let amount = value.to_u128().ok_or(BridgeContractError::InvalidAmount)?;
 
-            if emit_simple {
+            let existential_deposit = self.env().minimum_balance();
+
+            if amount < existential_deposit {
+                return Err(BridgeContractError::BelowExistentialDeposit);
+            }
+
+            if self.env().balance().saturating_sub(amount) < existential_deposit {
+                return Err(BridgeContractError::WouldReapAccount);
+            }
+
+            self.env()
+                .transfer(to, amount)
+                .map_err(|_| BridgeContractError::NativeTransferFailed)
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        fn record_outbound(
+            &mut self,
+            sender: MultiChainAddrHash,
+            token_address: MultiChainAddrHash,
+            token_amount: U256,
+            fee: U256,
+            target_chain: U256,
+            recipient: Option<MultiChainAddrHash>,
+            route: BridgeRoute,
+        ) -> u64 {
+            let nonce = *self.next_outbound_nonce;
+            let expires_at = self
+                .env()
+                .block_timestamp()
+                .saturating_add(*self.outbound_ttl);
+
+            self.outbound_messages.insert(
+                nonce,
+                &OutboundMessage {
+                    sender: sender.clone(),
+                    token_address: token_address.clone(),
+                    token_amount,
+                    target_chain,
+                    expires_at,
+                    status: OutboundStatus::Pending,
+                    burned: route == BridgeRoute::CherryBurn,
+                },
+            );
+            *self.next_outbound_nonce = nonce + 1;
+
+            if *self.legacy_events {
+                let legacy_recipient = recipient.as_ref().unwrap_or(&sender).to_string();
+
+                match route {
+                    BridgeRoute::CherryLock | BridgeRoute::CherryBurn => {
+                        Self::env().emit_event(BridgeCherrySimple {
+                            token_amount: token_amount.to_decimal(),
+                            recipient: legacy_recipient,
+                        })
+                    }
+                    BridgeRoute::Token | BridgeRoute::Native => {
+                        Self::env().emit_event(BridgeOutSimple {
+                            token_address: token_address.to_string(),
+                            token_amount: token_amount.to_decimal(),
+                            recipient: legacy_recipient,
+                            target_chain: Some(target_chain.to_decimal()),
+                        })
+                    }
+                }
+            }
+
+            Self::env().emit_event(BridgedOut {
+                version: EVENT_SCHEMA_VERSION,
+                token: token_address.digest(),
+                sender: sender.digest(),
+                target_chain,
+                nonce,
+                token_address,
+                sender_address: sender,
+                recipient,
+                amount: token_amount,
+                fee,
+                route,
+                expires_at,
+            });
+
+            nonce
+        }
+
+        fn emit_bridged_in(
+            &self,
+            token_address: MultiChainAddrHash,
+            token_amount: U256,
+            recipient: MultiChainAddrHash,
+            source_chain: U256,
+            source_nonce: Option<u64>,
+            route: BridgeRoute,
+        ) {
+            if *self.legacy_events {
                 Self::env().emit_event(BridgeInSimple {
                     token_address: token_address.to_string(),
-                    token_amount: token_amount_str,
-                    recipient: recipient_str,
-                    from_chain: Some(from_chain_str),
+                    token_amount: token_amount.to_decimal(),
+                    recipient: recipient.to_string(),
+                    from_chain: Some(source_chain.to_decimal()),
                 });
             }
 
+            Self::env().emit_event(BridgedIn {
+                version: EVENT_SCHEMA_VERSION,
+                token: token_address.digest(),
+                recipient: recipient.digest(),
+                source_chain,
+                source_nonce,
+                token_address,
+                recipient_address: recipient,
+                amount: token_amount,
+                fee: U256::new_zero(),
+                route,
+            });
+        }
+
+        #[ink(message)]
+        pub fn legacy_events(&self) -> bool {
+            *self.legacy_events
+        }
+
+        /// Enables the compatibility mode in which bridge actions also emit the
+        /// legacy `BridgeOutSimple`, `BridgeInSimple` and `BridgeCherrySimple`
+        /// string events next to the versioned ones.
+        #[ink(message)]
+        pub fn set_legacy_events(&mut self, enabled: bool) -> Result<(), BridgeContractError> {
+            self.ensure_owner()?;
+
+            *self.legacy_events = enabled;
+
             Ok(())
         }
 
+        fn local_account(to: &MultiChainAddrHash) -> Result<AccountId, BridgeContractError> {
+            to.to_account_bytes()
+                .map(AccountId::from)
+                .ok_or(BridgeContractError::InvalidAddress)
+        }
+
+        /// Adds `token` to the token index the first time it gets a holder,
+        /// a PSP22 contract or a locked balance. A token is indexed once it
+        /// has a holder count entry, even a zero one.
+        fn index_token(&mut self, token: &MultiChainAddrHash) {
+            if !self.token_holder_count.contains(token) {
+                self.tokens.insert(*self.token_count, token);
+                *self.token_count += 1;
+                self.token_holder_count.insert(token, &0);
+            }
+        }
+
+        fn set_balance(
+            &mut self,
+            owner: &MultiChainAddrHash,
+            token: &MultiChainAddrHash,
+            value: &U256,
+        ) {
+            if !self.balances.contains((owner, token)) {
+                if *value == U256::new_zero() {
+                    return;
+                }
+
+                self.index_token(token);
+
+                let count = self.token_holder_count.get(token).unwrap_or_default();
+
+                self.token_holders.insert((token, count), owner);
+                self.token_holder_count.insert(token, &(count + 1));
+
+                #[cfg(test)]
+                crate::invariants::ledger::record_holder(token, owner);
+            }
+
+            self.balances.insert((owner, token), value);
+        }
+
+        fn page(offset: u32, limit: u32, count: u32) -> core::ops::Range<u32> {
+            let start = offset.min(count);
+            let end = start.saturating_add(limit.min(MAX_PAGE_SIZE)).min(count);
+
+            start..end
+        }
+
         #[ink(message)]
-        pub fn bridge_out(
+        pub fn token_count(&self) -> u32 {
+            *self.token_count
+        }
+
+        #[ink(message)]
+        pub fn tokens(&self, offset: u32, limit: u32) -> Vec<MultiChainAddrHash> {
+            Self::page(offset, limit, *self.token_count)
+                .filter_map(|i| self.tokens.get(i))
+                .collect()
+        }
+
+        #[ink(message)]
+        pub fn holder_count(&self, token: MultiChainAddrHash) -> u32 {
+            self.token_holder_count.get(token).unwrap_or_default()
+        }
+
+        #[ink(message)]
+        pub fn holders_of(
+            &self,
+            token: MultiChainAddrHash,
+            offset: u32,
+            limit: u32,
+        ) -> Vec<(MultiChainAddrHash, U256)> {
+            Self::page(offset, limit, self.holder_count(token.clone()))
+                .filter_map(|i| self.token_holders.get((&token, i)))
+                .map(|holder| {
+                    let balance = self.balance_of(holder.clone(), token.clone());
+
+                    (holder, balance)
+                })
+                .collect()
+        }
+
+        #[ink(message)]
+        pub fn allowance_count(&self, owner: MultiChainAddrHash) -> u32 {
+            self.owner_allowance_count.get(owner).unwrap_or_default()
+        }
+
+        #[ink(message)]
+        pub fn allowances_of(
+            &self,
+            owner: MultiChainAddrHash,
+            offset: u32,
+            limit: u32,
+        ) -> Vec<(MultiChainAddrHash, MultiChainAddrHash, U256)> {
+            Self::page(offset, limit, self.allowance_count(owner.clone()))
+                .filter_map(|i| self.owner_allowances.get((&owner, i)))
+                .map(|(spender, token)| {
+                    let value = self.allowance(owner.clone(), spender.clone(), token.clone());
+
+                    (spender, token, value)
+                })
+                .collect()
+        }
+
+        #[ink(message)]
+        pub fn allowance_owner_count(&self) -> u32 {
+            *self.allowance_owner_count
+        }
+
+        /// Every account that has ever given an allowance, in the order of
+        /// their first approval.
+        #[ink(message)]
+        pub fn allowance_owners(&self, offset: u32, limit: u32) -> Vec<MultiChainAddrHash> {
+            Self::page(offset, limit, *self.allowance_owner_count)
+                .filter_map(|i| self.allowance_owners.get(i))
+                .collect()
+        }
+
+        fn ledger_changed(&self) {
+            #[cfg(test)]
+            crate::invariants::ledger::assert_supply_matches(self);
+        }
+
+        /// Sums the balances of one page of `token`'s holders. `holders` is
+        /// the total holder count, and `consistent` is only set by a page that
+        /// covers every holder; tokens with more holders than fit in a page
+        /// are audited by adding up the pages.
+        #[ink(message)]
+        pub fn audit_token(
+            &self,
+            token: MultiChainAddrHash,
+            offset: u32,
+            limit: u32,
+        ) -> TokenAudit {
+            let total_supply = self.total_supply(token.clone());
+            let holders = self.holder_count(token.clone());
+            let page = Self::page(offset, limit, holders);
+            let complete = page.start == 0 && page.end == holders;
+
+            let sum_of_balances = self
+                .holders_of(token, offset, limit)
+                .iter()
+                .fold(U256::new_zero(), |sum, (_, balance)| {
+                    U256::add_a_with_b(&sum, balance)
+                });
+
+            TokenAudit {
+                total_supply,
+                sum_of_balances,
+                holders,
+                consistent: complete && sum_of_balances == total_supply,
+            }
+        }
+
+        fn transfer_from_to(
             &mut self,
-            token_address: MultiChainAddrHash,
-            token_amount: U256,
-            target_chain: U256,
+            from: &MultiChainAddrHash,
+            to: &MultiChainAddrHash,
+            token: &MultiChainAddrHash,
+            value: &U256,
         ) -> Result<(), BridgeContractError> {
-            let caller = self.env().caller();
-            let from: MultiChainAddrHash = (caller.as_ref() as &[u8]).into();
+            if !self.total_supply.contains(token) {
+                return Err(BridgeContractError::UnknownToken);
+            }
 
-            let contract_hash = self.env().own_code_hash().unwrap();
-            let to = (contract_hash.as_ref() as &[u8]).into();
+            let from_balance = self.balance_of(from.clone(), token.clone());
 
-            self.transfer_from(&from, &to, &token_address, &token_amount)?;
+            if U256::a_greater_than_b(value, &from_balance) {
+                return Err(BridgeContractError::InsufficientBalance);
+            }
 
-            Self::env().emit_event(BridgeOutComplex {
-                token_address,
-                token_amount,
-                recipient: to,
-                target_chain: Some(target_chain),
+            let sub_from = U256::subtract_b_from_a(&from_balance, value);
+
+            self.set_balance(from, token, &sub_from);
+
+            let to_balance = self.balance_of(to.clone(), token.clone());
+
+            let add_to = U256::add_a_with_b(&to_balance, value);
+
+            self.set_balance(to, token, &add_to);
+            self.ledger_changed();
+
+            Self::env().emit_event(Transfer {
+                from: Some(from.to_string()),
+                to: Some(to.to_string()),
+                value_decimal: value.to_decimal(),
+                value_hex: value.to_hex(),
             });
 
             Ok(())
         }
 
         #[ink(message)]
-        pub fn bridge_out_string(
+        pub fn transfer_from(
             &mut self,
-            token_address_str: String,
-            token_amount_str: String,
-            target_chain_str: String,
-            emit_simple: bool,
+            from: MultiChainAddrHash,
+            to: MultiChainAddrHash,
+            token: MultiChainAddrHash,
+            value: U256,
         ) -> Result<(), BridgeContractError> {
-            let token_address = token_address_str.clone().into();
-            let token_amount = token_amount_str.clone().into();
-            let target_chain = target_chain_str.clone().into();
+            self.ensure_not_importing()?;
 
-            self.bridge_out(token_address, token_amount, target_chain)?;
+            let spender = self.caller_hash();
 
-            let caller = self.env().caller();
-            let recipient: MultiChainAddrHash = (caller.as_ref() as &[u8]).into();
+            self.transfer_from_as(&spender, &from, &to, &token, &value)
+        }
 
-            if emit_simple {
-                Self::env().emit_event(BridgeOutSimple {
-                    token_address: token_address_str,
-                    token_amount: token_amount_str,
-                    recipient: recipient.to_string(),
-                    target_chain: Some(target_chain_str),
-                });
+        fn transfer_from_as(
+            &mut self,
+            spender: &MultiChainAddrHash,
+            from: &MultiChainAddrHash,
+            to: &MultiChainAddrHash,
+            token: &MultiChainAddrHash,
+            value: &U256,
+        ) -> Result<(), BridgeContractError> {
+            let remaining = self.remaining_allowance(from, spender, token, value)?;
+
+            self.transfer_from_to(from, to, token, value)?;
+
+            if let Some(remaining) = remaining {
+                self.set_allowance(from, spender, token, &remaining);
             }
 
             Ok(())
         }
+
+        /// Allowance left to `spender` after spending `value` of `owner`'s
+        /// `token`, or `None` when nothing has to be deducted because the
+        /// spender is an operator or holds an infinite (`U256::new_ff`) allowance.
+        fn remaining_allowance(
+            &self,
+            owner: &MultiChainAddrHash,
+            spender: &MultiChainAddrHash,
+            token: &MultiChainAddrHash,
+            value: &U256,
+        ) -> Result<Option<U256>, BridgeContractError> {
+            if self.is_approved_for_all(owner.clone(), spender.clone()) {
+                return Ok(None);
+            }
+
+            let allowance = self
+                .allowances
+                .get((owner, spender, token))
+                .ok_or(BridgeContractError::NoAllowance)?;
+
+            if allowance == U256::new_ff() {
+                return Ok(None);
+            }
+
+            if U256::a_greater_than_b(value, &allowance) {
+                return Err(BridgeContractError::InsufficientAllowance);
+            }
+
+            Ok(Some(U256::subtract_b_from_a(&allowance, value)))
+        }
+
+        #[ink(message)]
+        pub fn transfer(
+            &mut self,
+            to: MultiChainAddrHash,
+            token: MultiChainAddrHash,
+            value: U256,
+        ) -> Result<(), BridgeContractError> {
+            self.ensure_not_importing()?;
+
+            let ohac = self.caller_hash();
+
+            self.transfer_from_to(&ohac, &to, &token, &value)
+        }
+
+        #[ink(message)]
+        pub fn balance_of(&self, owner: MultiChainAddrHash, token: MultiChainAddrHash) -> U256 {
+            self.balances.get((owner, token)).unwrap_or_default()
+        }
+
+        #[ink(message)]
+        pub fn allowance(
+            &self,
+            owner: MultiChainAddrHash,
+            spender: MultiChainAddrHash,
+            token: MultiChainAddrHash,
+        ) -> U256 {
+            self.allowances
+                .get((owner, spender, token))
+                .unwrap_or_default()
+        }
+
+        #[ink(message)]
+        pub fn total_supply(&self, token: MultiChainAddrHash) -> U256 {
+            self.total_supply.get(token).unwrap_or_default()
+        }
+
+        #[ink(message)]
+        pub fn approve(
+            &mut self,
+            spender: MultiChainAddrHash,
+            token: MultiChainAddrHash,
+            value: U256,
+        ) {
+            assert!(
+                self.ensure_not_importing().is_ok(),
+                "state import in progress"
+            );
+
+            let owner = self.caller_hash();
+
+            self.set_allowance(&owner, &spender, &token, &value);
+        }
+
+        #[ink(message)]
+        pub fn increase_allowance(
+            &mut self,
+            spender: MultiChainAddrHash,
+            token: MultiChainAddrHash,
+            delta_value: U256,
+        ) -> Result<(), BridgeContractError> {
+            self.ensure_not_importing()?;
+
+            let owner = self.caller_hash();
+            let current = self.allowance(owner.clone(), spender.clone(), token.clone());
+            let sum = U256::add_a_with_b(&current, &delta_value);
+
+            // A sum that wraps past 2^256 saturates to the infinite allowance.
+            let add_allowance = if U256::a_greater_than_b(&current, &sum) {
+                U256::new_ff()
+            } else {
+                sum
+            };
+
+            self.set_allowance(&owner, &spender, &token, &add_allowance);
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn decrease_allowance(
+            &mut self,
+            spender: MultiChainAddrHash,
+            token: MultiChainAddrHash,
+            delta_value: U256,
+        ) -> Result<(), BridgeContractError> {
+            self.ensure_not_importing()?;
+
+            let owner = self.caller_hash();
+            let current = self.allowance(owner.clone(), spender.clone(), token.clone());
+
+            if U256::a_greater_than_b(&delta_value, &current) {
+                return Err(BridgeContractError::InsufficientAllowance);
+            }
+
+            let sub_allowance = U256::subtract_b_from_a(&current, &delta_value);
+
+            self.set_allowance(&owner, &spender, &token, &sub_allowance);
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn permit_nonce(&self, owner: AccountId) -> u64 {
+            self.permit_nonces.get(owner).unwrap_or(0)
+        }
+
+        /// Digest an owner signs to authorize `permit` with their current nonce.
+        ///
+        /// The payload is domain-separated by a version tag and this contract's
+        /// address, so a signature can't be replayed against another deployment.
+        #[ink(message)]
+        pub fn permit_digest(
+            &self,
+            owner: AccountId,
+            spender: MultiChainAddrHash,
+            token: MultiChainAddrHash,
+            value: U256,
+            deadline: Timestamp,
+        ) -> [u8; 32] {
+            self.signing_digest(
+                PERMIT_DOMAIN,
+                &(
+                    owner,
+                    spender,
+                    token,
+                    value,
+                    deadline,
+                    self.permit_nonce(owner),
+                ),
+            )
+        }
+
+        /// Sets `spender`'s allowance over `owner`'s `token` from an ECDSA
+        /// signature of `permit_digest`, so a relayer can submit the approval.
+        ///
+        /// Only ECDSA is accepted: ink! 3 exposes no sr25519 verification to
+        /// contracts. `owner` must be the blake2-256 hash of the compressed
+        /// public key, the usual account derivation for ECDSA keys.
+        #[ink(message)]
+        pub fn permit(
+            &mut self,
+            owner: AccountId,
+            spender: MultiChainAddrHash,
+            token: MultiChainAddrHash,
+            value: U256,
+            deadline: Timestamp,
+            signature: [u8; 65],
+        ) -> Result<(), BridgeContractError> {
+            self.ensure_not_importing()?;
+
+            if self.env().block_timestamp() > deadline {
+                return Err(BridgeContractError::Expired);
+            }
+
+            let digest = self.permit_digest(owner, spender.clone(), token.clone(), value, deadline);
+
+            self.verify_signature(owner, &digest, &signature)?;
+            self.permit_nonces
+                .insert(owner, &(self.permit_nonce(owner) + 1));
+
+            let owner: MultiChainAddrHash = (owner.as_ref() as &[u8]).into();
+
+            self.set_allowance(&owner, &spender, &token, &value);
+
+            Ok(())
+        }
+
+        fn signing_digest<T: scale::Encode>(&self, domain: &[u8], payload: &T) -> [u8; 32] {
+            let mut digest = [0u8; 32];
+
+            ink_env::hash_encoded::<ink_env::hash::Blake2x256, _>(
+                &(domain, self.env().account_id(), payload),
+                &mut digest,
+            );
+
+            digest
+        }
+
+        fn verify_signature(
+            &self,
+            signer: AccountId,
+            digest: &[u8; 32],
+            signature: &[u8; 65],
+        ) -> Result<(), BridgeContractError> {
+            let public_key = self
+                .env()
+                .ecdsa_recover(signature, digest)
+                .map_err(|_| BridgeContractError::InvalidSignature)?;
+
+            let mut recovered = [0u8; 32];
+
+            ink_env::hash_bytes::<ink_env::hash::Blake2x256>(&public_key, &mut recovered);
+
+            if AccountId::from(recovered) != signer {
+                return Err(BridgeContractError::InvalidSignature);
+            }
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn balance_of_batch(
+            &self,
+            accounts_tokens: Vec<(MultiChainAddrHash, MultiChainAddrHash)>,
+        ) -> Vec<U256> {
+            accounts_tokens
+                .into_iter()
+                .map(|(owner, token)| self.balance_of(owner, token))
+                .collect()
+        }
+
+        #[ink(message)]
+        pub fn set_approval_for_all(&mut self, operator: MultiChainAddrHash, approved: bool) {
+            assert!(
+                self.ensure_not_importing().is_ok(),
+                "state import in progress"
+            );
+
+            let owner = self.caller_hash();
+
+            self.operators.insert((&owner, &operator), &approved);
+            self.env().emit_event(ApprovalForAll {
+                owner: owner.to_string(),
+                operator: operator.to_string(),
+                approved,
+            });
+        }
+
+        #[ink(message)]
+        pub fn is_approved_for_all(
+            &self,
+            owner: MultiChainAddrHash,
+            operator: MultiChainAddrHash,
+        ) -> bool {
+            self.operators.get((owner, operator)).unwrap_or(false)
+        }
+
+        #[ink(message)]
+        pub fn transfer_batch(
+            &mut self,
+            to: MultiChainAddrHash,
+            tokens_values: Vec<(MultiChainAddrHash, U256)>,
+        ) -> Result<(), BridgeContractError> {
+            self.ensure_not_importing()?;
+
+            let from = self.caller_hash();
+
+            for (token, value) in tokens_values.iter() {
+                self.transfer_from_to(&from, &to, token, value)?;
+            }
+
+            self.emit_transfer_batch(&from, &to, &tokens_values);
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn transfer_from_batch(
+            &mut self,
+            from: MultiChainAddrHash,
+            to: MultiChainAddrHash,
+            tokens_values: Vec<(MultiChainAddrHash, U256)>,
+        ) -> Result<(), BridgeContractError> {
+            for (token, value) in tokens_values.iter() {
+                self.transfer_from(from.clone(), to.clone(), token.clone(), *value)?;
+            }
+
+            self.emit_transfer_batch(&from, &to, &tokens_values);
+
+            Ok(())
+        }
+
+        fn emit_transfer_batch(
+            &self,
+            from: &MultiChainAddrHash,
+            to: &MultiChainAddrHash,
+            tokens_values: &[(MultiChainAddrHash, U256)],
+        ) {
+            self.env().emit_event(TransferBatch {
+                from: Some(from.to_string()),
+                to: Some(to.to_string()),
+                tokens: tokens_values.iter().map(|(t, _)| t.to_string()).collect(),
+                values_decimal: tokens_values.iter().map(|(_, v)| v.to_decimal()).collect(),
+                values_hex: tokens_values.iter().map(|(_, v)| v.to_hex()).collect(),
+            });
+        }
+
+        fn set_allowance(
+            &mut self,
+            owner: &MultiChainAddrHash,
+            spender: &MultiChainAddrHash,
+            token: &MultiChainAddrHash,
+            value: &U256,
+        ) {
+            if !self.allowances.contains((owner, spender, token)) {
+                let count = self.allowance_count(owner.clone());
+
+                if count == 0 {
+                    self.allowance_owners
+                        .insert(*self.allowance_owner_count, owner);
+                    *self.allowance_owner_count += 1;
+                }
+
+                self.owner_allowances
+                    .insert((owner, count), &(spender.clone(), token.clone()));
+                self.owner_allowance_count.insert(owner, &(count + 1));
+            }
+
+            self.allowances.insert((owner, spender, token), value);
+            self.env().emit_event(Approval {
+                owner: owner.to_string(),
+                spender: spender.to_string(),
+                value_decimal: value.to_decimal(),
+                value_hex: value.to_hex(),
+            });
+        }
+
+        fn caller_hash(&self) -> MultiChainAddrHash {
+            let caller = self.env().caller();
+
+            (caller.as_ref() as &[u8]).into()
+        }
+
+        #[ink(message)]
+        pub fn cherry_token(&self) -> MultiChainAddrHash {
+            self.cherry_token.clone()
+        }
+
+        #[ink(message)]
+        pub fn set_cherry_token(
+            &mut self,
+            token: MultiChainAddrHash,
+        ) -> Result<(), BridgeContractError> {
+            self.ensure_owner()?;
+
+            let old_token = core::mem::replace(&mut *self.cherry_token, token.clone());
+
+            Self::env().emit_event(CherryTokenUpdated {
+                old_token,
+                new_token: token,
+            });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn bridge_cherry(
+            &mut self,
+            token_amount: U256,
+            target_chain: U256,
+            recipient: MultiChainAddrHash,
+        ) -> Result<(), BridgeContractError> {
+            self.ensure_not_paused()?;
+            Self::ensure_nonzero(&token_amount)?;
+
+            let chain = self
+                .chains
+                .get(target_chain)
+                .ok_or(BridgeContractError::UnknownChain)?;
+            let token = self.cherry_token.clone();
+            let (net_amount, fee) = Self::split_fee(&token_amount, chain.cherry_fee_bps)?;
+            let burned = chain.cherry_mode == CherryMode::Burn;
+
+            if burned {
+                if self.psp22_tokens.contains(&token) {
+                    return Err(BridgeContractError::UnsupportedOperation);
+                }
+
+                self.burn(&self.caller_hash(), &token, &net_amount)?;
+                self.pull_into_custody(self.env().caller(), &token, &fee)?;
+            } else {
+                self.pull_into_custody(self.env().caller(), &token, &token_amount)?;
+                self.lock(&token, &net_amount);
+            }
+
+            let route = if burned {
+                BridgeRoute::CherryBurn
+            } else {
+                BridgeRoute::CherryLock
+            };
+
+            self.collect_fee(&token, &fee);
+            self.record_outbound(
+                self.caller_hash(),
+                token,
+                net_amount,
+                fee,
+                target_chain,
+                Some(recipient),
+                route,
+            );
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn bridge_cherry_string(
+            &mut self,
+            token_amount_str: String,
+            target_chain_str: String,
+            recipient_str: String,
+        ) -> Result<(), BridgeContractError> {
+            let token_amount = U256::try_from(token_amount_str)?;
+            let target_chain = U256::try_from(target_chain_str)?;
+            let recipient = recipient_str.into();
+
+            self.bridge_cherry(token_amount, target_chain, recipient)
+        }
+
+        #[ink(message)]
+        pub fn set_cherry_attester(
+            &mut self,
+            attester: AccountId,
+            enabled: bool,
+        ) -> Result<(), BridgeContractError> {
+            self.ensure_owner()?;
+
+            self.cherry_attesters.insert(attester, &enabled);
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn is_cherry_attester(&self, account: AccountId) -> bool {
+            account == self.owner || self.cherry_attesters.get(account).unwrap_or(false)
+        }
+
+        #[ink(message)]
+        pub fn receive_cherry(
+            &mut self,
+            token_amount: U256,
+            recipient: MultiChainAddrHash,
+            from_chain: U256,
+            source_nonce: u64,
+        ) -> Result<(), BridgeContractError> {
+            self.ensure_not_paused()?;
+            Self::ensure_nonzero(&token_amount)?;
+
+            if !self.is_cherry_attester(self.env().caller()) {
+                return Err(BridgeContractError::Unauthorized);
+            }
+
+            let chain = self
+                .chains
+                .get(from_chain)
+                .ok_or(BridgeContractError::UnknownChain)?;
+
+            self.ensure_not_processed(&from_chain, source_nonce)?;
+
+            let token = self.cherry_token.clone();
+            let minted = chain.cherry_mode == CherryMode::Burn;
+
+            if minted {
+                if self.psp22_tokens.contains(&token) {
+                    return Err(BridgeContractError::UnsupportedOperation);
+                }
+
+                self.mint(&recipient, &token, &token_amount);
+            } else {
+                self.payout(&token, &recipient, &token_amount)?;
+            }
+
+            self.mark_inbound_processed(&from_chain, source_nonce);
+
+            let route = if minted {
+                BridgeRoute::CherryBurn
+            } else {
+                BridgeRoute::CherryLock
+            };
+
+            self.emit_bridged_in(
+                token,
+                token_amount,
+                recipient,
+                from_chain,
+                Some(source_nonce),
+                route,
+            );
+
+            Ok(())
+        }
+
+        /// Inbound messages are identified by their source chain and the
+        /// outbound nonce they were given there. The source numbers messages
+        /// of every route from one counter, so all routes share this set.
+        fn ensure_not_processed(
+            &self,
+            from_chain: &U256,
+            source_nonce: u64,
+        ) -> Result<(), BridgeContractError> {
+            if self.processed_inbound.contains((from_chain, source_nonce)) {
+                return Err(BridgeContractError::AlreadyProcessed);
+            }
+
+            Ok(())
+        }
+
+        fn mark_inbound_processed(&mut self, from_chain: &U256, source_nonce: u64) {
+            self.processed_inbound
+                .insert((from_chain, source_nonce), &true);
+            self.processed_inbound_index
+                .insert(*self.processed_inbound_count, &(*from_chain, source_nonce));
+            *self.processed_inbound_count += 1;
+        }
+
+        #[ink(message)]
+        pub fn bridge_in(
+            &mut self,
+            token_address: MultiChainAddrHash,
+            token_amount: U256,
+            recipient: MultiChainAddrHash,
+            from_chain: U256,
+            source_nonce: u64,
+        ) -> Result<(), BridgeContractError> {
+            self.ensure_not_paused()?;
+            self.ensure_relayer()?;
+            Self::ensure_nonzero(&token_amount)?;
+            self.ensure_not_processed(&from_chain, source_nonce)?;
+
+            // Tokens this side never locked are paid from the relayer's own
+            // float, which only a relayer may spend on an inbound message.
+            if self.psp22_tokens.contains(&token_address)
+                || self.locked_balances.contains(&token_address)
+            {
+                self.payout(&token_address, &recipient, &token_amount)?;
+            } else {
+                self.transfer(recipient.clone(), token_address.clone(), token_amount)?;
+            }
+
+            self.mark_inbound_processed(&from_chain, source_nonce);
+            self.emit_bridged_in(
+                token_address,
+                token_amount,
+                recipient,
+                from_chain,
+                Some(source_nonce),
+                BridgeRoute::Token,
+            );
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn bridge_in_string(
+            &mut self,
+            token_address_str: String,
+            token_amount_str: String,
+            recipient_str: String,
+            from_chain_str: String,
+            source_nonce: u64,
+        ) -> Result<(), BridgeContractError> {
+            let token_address = token_address_str.into();
+            let token_amount = U256::try_from(token_amount_str)?;
+            let recipient = recipient_str.into();
+            let from_chain = U256::try_from(from_chain_str)?;
+
+            self.bridge_in(
+                token_address,
+                token_amount,
+                recipient,
+                from_chain,
+                source_nonce,
+            )
+        }
+
+        #[ink(message)]
+        pub fn bridge_out(
+            &mut self,
+            token_address: MultiChainAddrHash,
+            token_amount: U256,
+            target_chain: U256,
+        ) -> Result<(), BridgeContractError> {
+            self.ensure_not_paused()?;
+            Self::ensure_nonzero(&token_amount)?;
+
+            let (net_amount, fee) =
+                Self::split_fee(&token_amount, self.chain_fee_bps(&target_chain)?)?;
+
+            self.pull_into_custody(self.env().caller(), &token_address, &token_amount)?;
+            self.lock_outbound(
+                self.caller_hash(),
+                token_address,
+                net_amount,
+                fee,
+                target_chain,
+            );
+
+            Ok(())
+        }
+
+        /// Bridges out on behalf of `request.signer`, who signed the request
+        /// with ECDSA (see `permit`) instead of submitting it.
+        ///
+        /// The submitting relayer is reimbursed `relay_fee_bps` of the amount
+        /// left after the chain fee, paid in the bridged token. The signer's
+        /// tokens are pulled with the bridge as spender, so the signer must
+        /// have approved it beforehand, or approve it in the same call through
+        /// `bridge_out_with_permit`.
+        #[ink(message)]
+        pub fn bridge_out_signed(
+            &mut self,
+            request: SignedOutboundRequest,
+            signature: [u8; 65],
+        ) -> Result<(), BridgeContractError> {
+            self.ensure_not_paused()?;
+            self.ensure_relayer()?;
+
+            if self.env().block_timestamp() > request.deadline {
+                return Err(BridgeContractError::Expired);
+            }
+
+            if request.nonce != self.permit_nonce(request.signer) {
+                return Err(BridgeContractError::InvalidNonce);
+            }
+
+            let digest = self.signing_digest(BRIDGE_OUT_DOMAIN, &request);
+
+            self.verify_signature(request.signer, &digest, &signature)?;
+
+            let SignedOutboundRequest {
+                signer,
+                request:
+                    OutboundRequest {
+                        token_address,
+                        token_amount,
+                        target_chain,
+                    },
+                nonce,
+                max_fee,
+                ..
+            } = request;
+
+            Self::ensure_nonzero(&token_amount)?;
+
+            let (net_amount, fee) =
+                Self::split_fee(&token_amount, self.chain_fee_bps(&target_chain)?)?;
+            let (net_amount, relay_fee) = Self::split_fee(&net_amount, *self.relay_fee_bps)?;
+
+            if U256::a_greater_than_b(&U256::add_a_with_b(&fee, &relay_fee), &max_fee) {
+                return Err(BridgeContractError::FeeTooHigh);
+            }
+
+            self.permit_nonces.insert(signer, &(nonce + 1));
+            self.pull_into_custody(signer, &token_address, &token_amount)?;
+
+            if relay_fee.to_u128() != Some(0) {
+                self.send_from_custody(&token_address, &self.caller_hash(), &relay_fee)?;
+            }
+
+            let sender: MultiChainAddrHash = (signer.as_ref() as &[u8]).into();
+
+            self.lock_outbound(sender, token_address, net_amount, fee, target_chain);
+
+            Ok(())
+        }
+
+        /// Applies `permit` and then `bridge_out_signed` in one call, so a
+        /// relayer can submit a wallet's approval together with its bridge-out.
+        ///
+        /// Both draw on the signer's permit nonce: the permit is signed with
+        /// the current nonce and the request with the one after it.
+        #[ink(message)]
+        pub fn bridge_out_with_permit(
+            &mut self,
+            permit: Permit,
+            permit_signature: [u8; 65],
+            request: SignedOutboundRequest,
+            signature: [u8; 65],
+        ) -> Result<(), BridgeContractError> {
+            let Permit {
+                owner,
+                spender,
+                token,
+                value,
+                deadline,
+            } = permit;
+
+            self.permit(owner, spender, token, value, deadline, permit_signature)?;
+            self.bridge_out_signed(request, signature)
+        }
+
+        /// Digest a signer signs to authorize `bridge_out_signed`.
+        #[ink(message)]
+        pub fn bridge_out_digest(&self, request: SignedOutboundRequest) -> [u8; 32] {
+            self.signing_digest(BRIDGE_OUT_DOMAIN, &request)
+        }
+
+        #[ink(message)]
+        pub fn relay_fee_bps(&self) -> u32 {
+            *self.relay_fee_bps
+        }
+
+        #[ink(message)]
+        pub fn set_relay_fee_bps(&mut self, fee_bps: u32) -> Result<(), BridgeContractError> {
+            self.ensure_owner()?;
+
+            if fee_bps > MAX_FEE_BPS {
+                return Err(BridgeContractError::InvalidFee);
+            }
+
+            *self.relay_fee_bps = fee_bps;
+
+            Ok(())
+        }
+
+        /// Outbound transfers are only accepted to chains configured with
+        /// `set_chain`; anything else could never be relayed and would only
+        /// come back through a refund.
+        fn chain_fee_bps(&self, chain_id: &U256) -> Result<u32, BridgeContractError> {
+            self.chains
+                .get(chain_id)
+                .map(|c| c.fee_bps)
+                .ok_or(BridgeContractError::UnknownChain)
+        }
+
+        fn lock_outbound(
+            &mut self,
+            sender: MultiChainAddrHash,
+            token_address: MultiChainAddrHash,
+            token_amount: U256,
+            fee: U256,
+            target_chain: U256,
+        ) {
+            self.lock(&token_address, &token_amount);
+            self.collect_fee(&token_address, &fee);
+            self.record_outbound(
+                sender,
+                token_address,
+                token_amount,
+                fee,
+                target_chain,
+                None,
+                BridgeRoute::Token,
+            );
+        }
+
+        #[ink(message)]
+        pub fn bridge_out_string(
+            &mut self,
+            token_address_str: String,
+            token_amount_str: String,
+            target_chain_str: String,
+        ) -> Result<(), BridgeContractError> {
+            let token_address = token_address_str.into();
+            let token_amount = U256::try_from(token_amount_str)?;
+            let target_chain = U256::try_from(target_chain_str)?;
+
+            self.bridge_out(token_address, token_amount, target_chain)
+        }
+
+        #[ink(message)]
+        pub fn bridge_in_batch(
+            &mut self,
+            messages: Vec<InboundMessage>,
+            mode: BatchMode,
+        ) -> Result<Vec<Result<(), BridgeContractError>>, BridgeContractError> {
+            let mut results = Vec::with_capacity(messages.len());
+
+            for message in messages {
+                let result = self.bridge_in(
+                    message.token_address,
+                    message.token_amount,
+                    message.recipient,
+                    message.from_chain,
+                    message.source_nonce,
+                );
+
+                if mode == BatchMode::AllOrNothing {
+                    result?;
+                    results.push(Ok(()));
+                } else {
+                    results.push(result);
+                }
+            }
+
+            let (total, succeeded) = Self::batch_counts(&results);
+
+            Self::env().emit_event(BridgeInBatch {
+                total,
+                succeeded,
+                failed: total - succeeded,
+            });
+
+            Ok(results)
+        }
+
+        #[ink(message)]
+        pub fn bridge_out_batch(
+            &mut self,
+            requests: Vec<OutboundRequest>,
+            mode: BatchMode,
+        ) -> Result<Vec<Result<(), BridgeContractError>>, BridgeContractError> {
+            let mut results = Vec::with_capacity(requests.len());
+
+            for request in requests {
+                let result = self.bridge_out(
+                    request.token_address,
+                    request.token_amount,
+                    request.target_chain,
+                );
+
+                if mode == BatchMode::AllOrNothing {
+                    result?;
+                    results.push(Ok(()));
+                } else {
+                    results.push(result);
+                }
+            }
+
+            let (total, succeeded) = Self::batch_counts(&results);
+
+            Self::env().emit_event(BridgeOutBatch {
+                total,
+                succeeded,
+                failed: total - succeeded,
+            });
+
+            Ok(results)
+        }
+
+        fn batch_counts(results: &[Result<(), BridgeContractError>]) -> (u32, u32) {
+            let succeeded = results.iter().filter(|r| r.is_ok()).count();
+
+            (results.len() as u32, succeeded as u32)
+        }
+
+        #[ink(message)]
+        pub fn min_native_amount(&self) -> Balance {
+            *self.min_native_amount
+        }
+
+        #[ink(message)]
+        pub fn set_min_native_amount(
+            &mut self,
+            amount: Balance,
+        ) -> Result<(), BridgeContractError> {
+            self.ensure_owner()?;
+
+            *self.min_native_amount = amount;
+
+            Ok(())
+        }
+
+        #[ink(message, payable)]
+        pub fn bridge_out_native(
+            &mut self,
+            target_chain: U256,
+            recipient: MultiChainAddrHash,
+        ) -> Result<(), BridgeContractError> {
+            self.ensure_not_paused()?;
+
+            let value = self.env().transferred_value();
+
+            if value == 0 || value < *self.min_native_amount {
+                return Err(BridgeContractError::BelowMinimumAmount);
+            }
+
+            self.chain_fee_bps(&target_chain)?;
+
+            let token_address = MultiChainAddrHash::from(NATIVE_TOKEN.to_string());
+            let token_amount = U256::from_u128(value);
+
+            self.lock(&token_address, &token_amount);
+            self.record_outbound(
+                self.caller_hash(),
+                token_address,
+                token_amount,
+                U256::new_zero(),
+                target_chain,
+                Some(recipient),
+                BridgeRoute::Native,
+            );
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn bridge_in_native(
+            &mut self,
+            token_amount: U256,
+            recipient: MultiChainAddrHash,
+            from_chain: U256,
+            source_nonce: u64,
+        ) -> Result<(), BridgeContractError> {
+            self.ensure_not_paused()?;
+            Self::ensure_nonzero(&token_amount)?;
+
+            let token_address = MultiChainAddrHash::from(NATIVE_TOKEN.to_string());
+
+            self.ensure_relayer()?;
+            self.ensure_not_processed(&from_chain, source_nonce)?;
+            self.payout(&token_address, &recipient, &token_amount)?;
+
+            self.mark_inbound_processed(&from_chain, source_nonce);
+            self.emit_bridged_in(
+                token_address,
+                token_amount,
+                recipient,
+                from_chain,
+                Some(source_nonce),
+                BridgeRoute::Native,
+            );
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn paused(&self) -> bool {
+            *self.paused
+        }
+
+        #[ink(message)]
+        pub fn set_paused(&mut self, paused: bool) -> Result<(), BridgeContractError> {
+            self.ensure_owner()?;
+
+            *self.paused = paused;
+
+            Self::env().emit_event(PausedChanged { paused });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn set_relayer(
+            &mut self,
+            relayer: AccountId,
+            enabled: bool,
+        ) -> Result<(), BridgeContractError> {
+            self.ensure_owner()?;
+
+            self.relayers.insert(relayer, &enabled);
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn is_relayer(&self, account: AccountId) -> bool {
+            account == self.owner || self.relayers.get(account).unwrap_or(false)
+        }
+
+        #[ink(message)]
+        pub fn set_outbound_ttl(&mut self, ttl: Timestamp) -> Result<(), BridgeContractError> {
+            self.ensure_owner()?;
+
+            *self.outbound_ttl = ttl;
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn set_refund_fee_bps(&mut self, fee_bps: u32) -> Result<(), BridgeContractError> {
+            self.ensure_owner()?;
+
+            if fee_bps > MAX_FEE_BPS {
+                return Err(BridgeContractError::InvalidFee);
+            }
+
+            *self.refund_fee_bps = fee_bps;
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn storage_version(&self) -> u32 {
+            *self.storage_version
+        }
+
+        #[ink(message)]
+        pub fn upgrade_delay(&self) -> Timestamp {
+            *self.upgrade_delay
+        }
+
+        /// Lengthens the upgrade timelock. It can never be shortened, so a
+        /// compromised owner key cannot skip the delay users rely on to exit.
+        #[ink(message)]
+        pub fn set_upgrade_delay(&mut self, delay: Timestamp) -> Result<(), BridgeContractError> {
+            self.ensure_owner()?;
+
+            if delay < *self.upgrade_delay {
+                return Err(BridgeContractError::DelayTooShort);
+            }
+
+            *self.upgrade_delay = delay;
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn pending_upgrade(&self) -> Option<PendingUpgrade> {
+            *self.pending_upgrade
+        }
+
+        /// Schedules a switch to `code_hash`, replacing any upgrade already
+        /// scheduled. It can be applied with `upgrade` once `upgrade_delay`
+        /// has passed.
+        #[ink(message)]
+        pub fn schedule_upgrade(&mut self, code_hash: Hash) -> Result<(), BridgeContractError> {
+            self.ensure_owner()?;
+
+            let ready_at = self
+                .env()
+                .block_timestamp()
+                .checked_add(*self.upgrade_delay)
+                .ok_or(BridgeContractError::DelayOverflow)?;
+
+            *self.pending_upgrade = Some(PendingUpgrade {
+                code_hash,
+                ready_at,
+            });
+
+            Self::env().emit_event(UpgradeScheduled {
+                code_hash,
+                ready_at,
+            });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn cancel_upgrade(&mut self) -> Result<(), BridgeContractError> {
+            self.ensure_owner()?;
+
+            self.pending_upgrade
+                .take()
+                .ok_or(BridgeContractError::NoPendingUpgrade)?;
+
+            Ok(())
+        }
+
+        /// Replaces the contract code with the scheduled `code_hash`, keeping
+        /// all storage. The new code runs from the next call on, which should
+        /// be `migrate`.
+        #[ink(message)]
+        pub fn upgrade(&mut self, code_hash: Hash) -> Result<(), BridgeContractError> {
+            self.ensure_owner()?;
+
+            let pending = self
+                .pending_upgrade
+                .filter(|pending| pending.code_hash == code_hash)
+                .ok_or(BridgeContractError::NoPendingUpgrade)?;
+
+            if self.env().block_timestamp() < pending.ready_at {
+                return Err(BridgeContractError::UpgradeTimelocked);
+            }
+
+            let mut code = [0u8; 32];
+
+            code.copy_from_slice(code_hash.as_ref());
+            *self.pending_upgrade = None;
+
+            ink_env::set_code_hash(&code).map_err(|_| BridgeContractError::UpgradeFailed)?;
+
+            Self::env().emit_event(Upgraded {
+                code_hash,
+                storage_version: *self.storage_version,
+            });
+
+            Ok(())
+        }
+
+        /// Brings storage written by older code up to `STORAGE_VERSION`, one
+        /// version at a time. Does nothing when storage is already current.
+        #[ink(message)]
+        pub fn migrate(&mut self) -> Result<u32, BridgeContractError> {
+            self.ensure_owner()?;
+
+            let from_version = *self.storage_version;
+
+            if from_version >= STORAGE_VERSION {
+                return Ok(from_version);
+            }
+
+            for version in from_version..STORAGE_VERSION {
+                self.migrate_from(version);
+            }
+
+            *self.storage_version = STORAGE_VERSION;
+
+            Self::env().emit_event(Migrated {
+                from_version,
+                to_version: STORAGE_VERSION,
+            });
+
+            Ok(STORAGE_VERSION)
+        }
+
+        /// Converts storage from `version` to `version + 1`. Releases that
+        /// change the `balances` or `allowances` layout add their rewrite
+        /// here, keyed by the version they migrate from.
+        fn migrate_from(&mut self, version: u32) {
+            match version {
+                // Version 0 is storage written before versioning, which lacks
+                // the version 1 fields and so reads them as defaults. Only the
+                // upgrade delay needs a real value; an outbound TTL of zero
+                // would also expire every new message at once. Allowances and
+                // inbound nonces from before versioning were never indexed and
+                // are not exported.
+                0 => {
+                    *self.upgrade_delay = DEFAULT_UPGRADE_DELAY;
+
+                    if *self.outbound_ttl == 0 {
+                        *self.outbound_ttl = DEFAULT_OUTBOUND_TTL;
+                    }
+                }
+                _ => unreachable!("no migration from storage version {}", version),
+            }
+        }
+
+        /// Returns one page of `section` as a SCALE-encoded `StateChunk`, for
+        /// moving the ledger to a new deployment with `import_state`. Collected
+        /// fees travel with their token; chain configuration, fee rates and
+        /// roles are not exported.
+        #[ink(message)]
+        pub fn export_state(&self, section: StateSection, offset: u32, limit: u32) -> Vec<u8> {
+            let chunk = match section {
+                StateSection::Tokens => StateChunk::Tokens(
+                    self.tokens(offset, limit)
+                        .into_iter()
+                        .map(|token| TokenState {
+                            metadata: self.token_metadata(token.clone()).unwrap_or_default(),
+                            total_supply: self.total_supply(token.clone()),
+                            locked: self.locked_balances.get(&token),
+                            psp22_contract: self.psp22_contract_of(token.clone()),
+                            collected_fees: self.collected_fees(token.clone()),
+                            token,
+                        })
+                        .collect(),
+                ),
+                StateSection::Balances(token) => StateChunk::Balances {
+                    balances: self.holders_of(token.clone(), offset, limit),
+                    token,
+                },
+                StateSection::Allowances(owner) => StateChunk::Allowances {
+                    allowances: self.allowances_of(owner.clone(), offset, limit),
+                    owner,
+                },
+                StateSection::Outbound => {
+                    let count = u32::try_from(*self.next_outbound_nonce).unwrap_or(u32::MAX);
+
+                    StateChunk::Outbound {
+                        next_nonce: *self.next_outbound_nonce,
+                        messages: Self::page(offset, limit, count)
+                            .filter_map(|nonce| {
+                                let nonce = u64::from(nonce);
+
+                                self.outbound_message(nonce).map(|message| (nonce, message))
+                            })
+                            .collect(),
+                    }
+                }
+                StateSection::Inbound => StateChunk::Inbound(
+                    Self::page(offset, limit, *self.processed_inbound_count)
+                        .filter_map(|i| self.processed_inbound_index.get(i))
+                        .collect(),
+                ),
+            };
+
+            scale::Encode::encode(&chunk)
+        }
+
+        /// The checksum to pass to `begin_import` for `chunks`, imported in
+        /// order over any number of `import_state` calls.
+        pub fn state_checksum(chunks: &[Vec<u8>]) -> [u8; 32] {
+            chunks.iter().fold([0u8; 32], |checksum, chunk| {
+                Self::chain_checksum(checksum, chunk)
+            })
+        }
+
+        fn chain_checksum(checksum: [u8; 32], chunk: &[u8]) -> [u8; 32] {
+            let mut output = [0u8; 32];
+
+            ink_env::hash_encoded::<ink_env::hash::Blake2x256, _>(&(checksum, chunk), &mut output);
+
+            output
+        }
+
+        /// Opens an import of the chunks whose `state_checksum` is `checksum`.
+        /// Only the owner may import, only once, and only while the ledger is
+        /// still empty, i.e. right after `new_with_allocations` with no
+        /// allocations. Until `finalize_import` succeeds, every message that
+        /// changes the ledger fails with `ImportInProgress`.
+        #[ink(message)]
+        pub fn begin_import(&mut self, checksum: [u8; 32]) -> Result<(), BridgeContractError> {
+            self.ensure_owner()?;
+
+            if *self.state_imported
+                || self.expected_import_checksum.is_some()
+                || *self.token_count != 0
+                || *self.next_outbound_nonce != 0
+            {
+                return Err(BridgeContractError::ImportNotAllowed);
+            }
+
+            *self.expected_import_checksum = Some(checksum);
+
+            Ok(())
+        }
+
+        /// Loads chunks produced by `export_state` into this deployment, after
+        /// `begin_import`. Large ledgers are imported over several calls.
+        #[ink(message)]
+        pub fn import_state(&mut self, chunks: Vec<Vec<u8>>) -> Result<(), BridgeContractError> {
+            self.ensure_owner()?;
+
+            if *self.state_imported || self.expected_import_checksum.is_none() {
+                return Err(BridgeContractError::ImportNotAllowed);
+            }
+
+            let decoded = chunks
+                .iter()
+                .map(|chunk| <StateChunk as scale::Decode>::decode(&mut &chunk[..]))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| BridgeContractError::InvalidStateChunk)?;
+
+            for (chunk, bytes) in decoded.into_iter().zip(&chunks) {
+                self.import_chunk(chunk);
+                *self.import_checksum = Self::chain_checksum(*self.import_checksum, bytes);
+                *self.imported_chunks += 1;
+            }
+
+            Ok(())
+        }
+
+        /// Closes the import and opens the ledger once the imported chunks
+        /// hash to the checksum given to `begin_import`. On a mismatch the
+        /// ledger stays closed: missing chunks can still be imported, but
+        /// reordered or altered ones leave a deployment that must be replaced.
+        #[ink(message)]
+        pub fn finalize_import(&mut self) -> Result<(), BridgeContractError> {
+            self.ensure_owner()?;
+
+            let expected = match *self.expected_import_checksum {
+                Some(expected) if !*self.state_imported => expected,
+                _ => return Err(BridgeContractError::ImportNotAllowed),
+            };
+
+            if *self.import_checksum != expected {
+                return Err(BridgeContractError::ChecksumMismatch);
+            }
+
+            *self.state_imported = true;
+
+            Self::env().emit_event(StateImported {
+                chunks: *self.imported_chunks,
+                checksum: expected,
+            });
+
+            Ok(())
+        }
+
+        fn import_chunk(&mut self, chunk: StateChunk) {
+            match chunk {
+                StateChunk::Tokens(tokens) => {
+                    for state in tokens {
+                        self.index_token(&state.token);
+                        self.token_metadata.insert(&state.token, &state.metadata);
+                        self.total_supply.insert(&state.token, &state.total_supply);
+
+                        if let Some(locked) = state.locked {
+                            self.locked_balances.insert(&state.token, &locked);
+                        }
+
+                        if let Some(contract) = state.psp22_contract {
+                            self.psp22_tokens.insert(&state.token, &contract);
+                        }
+
+                        if state.collected_fees != U256::new_zero() {
+                            self.collected_fees
+                                .insert(&state.token, &state.collected_fees);
+                        }
+                    }
+                }
+                StateChunk::Balances { token, balances } => {
+                    for (holder, balance) in balances {
+                        self.set_balance(&holder, &token, &balance);
+                    }
+                }
+                StateChunk::Allowances { owner, allowances } => {
+                    for (spender, token, value) in allowances {
+                        self.set_allowance(&owner, &spender, &token, &value);
+                    }
+                }
+                StateChunk::Outbound {
+                    next_nonce,
+                    messages,
+                } => {
+                    for (nonce, message) in messages {
+                        self.outbound_messages.insert(nonce, &message);
+                    }
+
+                    *self.next_outbound_nonce = self.next_outbound_nonce.max(next_nonce);
+                }
+                StateChunk::Inbound(nonces) => {
+                    for (chain, nonce) in nonces {
+                        if !self.processed_inbound.contains((&chain, nonce)) {
+                            self.mark_inbound_processed(&chain, nonce);
+                        }
+                    }
+                }
+            }
+        }
+
+        #[ink(message)]
+        pub fn outbound_message(&self, nonce: u64) -> Option<OutboundMessage> {
+            self.outbound_messages.get(nonce)
+        }
+
+        #[ink(message)]
+        pub fn confirm_delivered(&mut self, nonce: u64) -> Result<(), BridgeContractError> {
+            self.set_outbound_status(nonce, OutboundStatus::Delivered)
+        }
+
+        #[ink(message)]
+        pub fn mark_failed(&mut self, nonce: u64) -> Result<(), BridgeContractError> {
+            self.set_outbound_status(nonce, OutboundStatus::Failed)?;
+
+            Self::env().emit_event(BridgeOutFailed { nonce });
+
+            Ok(())
+        }
+
+        fn set_outbound_status(
+            &mut self,
+            nonce: u64,
+            status: OutboundStatus,
+        ) -> Result<(), BridgeContractError> {
+            if !self.is_relayer(self.env().caller()) {
+                return Err(BridgeContractError::Unauthorized);
+            }
+
+            self.ensure_not_importing()?;
+
+            let mut message = self
+                .outbound_messages
+                .get(nonce)
+                .ok_or(BridgeContractError::UnknownOutboundMessage)?;
+
+            if message.status != OutboundStatus::Pending {
+                return Err(BridgeContractError::OutboundNotPending);
+            }
+
+            message.status = status;
+            self.outbound_messages.insert(nonce, &message);
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn refund(&mut self, nonce: u64) -> Result<(), BridgeContractError> {
+            self.ensure_not_paused()?;
+
+            let mut message = self
+                .outbound_messages
+                .get(nonce)
+                .ok_or(BridgeContractError::UnknownOutboundMessage)?;
+
+            // The destination never sees `expires_at`, so a pending message
+            // can still be delivered after it. Only a relayer's `mark_failed`
+            // settles that it never will be.
+            if message.status != OutboundStatus::Failed {
+                return Err(BridgeContractError::NotRefundable);
+            }
+
+            let fee = message
+                .token_amount
+                .apply_bps(*self.refund_fee_bps)
+                .ok_or(BridgeContractError::InvalidAmount)?;
+            let token_amount = U256::subtract_b_from_a(&message.token_amount, &fee);
+
+            // The fee stays in custody as a collected fee on both routes, so
+            // it is withdrawn like any other fee and never has to clear the
+            // existential deposit on its own.
+            if message.burned {
+                self.mint(&message.sender, &message.token_address, &token_amount);
+                self.mint(&self.custody(), &message.token_address, &fee);
+            } else {
+                self.payout(&message.token_address, &message.sender, &token_amount)?;
+                self.unlock(&message.token_address, &fee)?;
+            }
+
+            self.collect_fee(&message.token_address, &fee);
+
+            message.status = OutboundStatus::Refunded;
+            self.outbound_messages.insert(nonce, &message);
+
+            Self::env().emit_event(BridgeOutRefunded {
+                nonce,
+                token_address: message.token_address,
+                token_amount,
+                fee,
+                recipient: message.sender,
+            });
+
+            Ok(())
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::helpers::psp22::mock;
+        use ink_env::DefaultEnvironment;
+        use ink_lang as ink;
+
+        type Event = <Bridge as ink::reflect::ContractEventBase>::Type;
+
+        const CONTRACT: [u8; 32] = [0x07; 32];
+        const PSP22_CONTRACT: [u8; 32] = [0x08; 32];
+
+        fn hash(s: &str) -> MultiChainAddrHash {
+            MultiChainAddrHash::from(s.to_string())
+        }
+
+        fn account_hash(account: AccountId) -> MultiChainAddrHash {
+            (account.as_ref() as &[u8]).into()
+        }
+
+        fn accounts() -> ink_env::test::DefaultAccounts<DefaultEnvironment> {
+            ink_env::test::default_accounts::<DefaultEnvironment>()
+        }
+
+        fn set_caller(account: AccountId) {
+            ink_env::test::set_caller::<DefaultEnvironment>(account);
+        }
+
+        /// A bridge owned by Alice, with chain 2 registered without fees.
+        fn new_bridge() -> Bridge {
+            ink_env::test::set_callee::<DefaultEnvironment>(CONTRACT.into());
+            set_caller(accounts().alice);
+
+            let mut bridge = Bridge::new(
+                hash("NATIVE_LEDGER_TOKEN"),
+                U256::from_u128(1_000),
+                hash("CHER"),
+            );
+
+            assert_eq!(
+                bridge.set_chain(
+                    U256::from_u128(2),
+                    Some(chain_config(0, 0, CherryMode::Lock))
+                ),
+                Ok(())
+            );
+
+            bridge
+        }
+
+        fn new_bridge_with_psp22() -> (Bridge, MultiChainAddrHash) {
+            let mut bridge = new_bridge();
+            let token = hash("PSP22_TOKEN");
+
+            assert!(bridge
+                .register_psp22_token(token.clone(), PSP22_CONTRACT.into())
+                .is_ok());
+
+            (bridge, token)
+        }
+
+        #[ink::test]
+        fn bridge_out_psp22_moves_tokens_into_custody() {
+            let (mut bridge, token) = new_bridge_with_psp22();
+            let alice = accounts().alice;
+
+            mock::set_balance(PSP22_CONTRACT.into(), alice, 100);
+            mock::approve(PSP22_CONTRACT.into(), alice, CONTRACT.into(), 100);
+
+            assert!(bridge
+                .bridge_out(token.clone(), U256::from_u128(60), U256::from_u128(2))
+                .is_ok());
+
+            assert_eq!(mock::balance_of(PSP22_CONTRACT.into(), alice), 40);
+            assert_eq!(mock::balance_of(PSP22_CONTRACT.into(), CONTRACT.into()), 60);
+            assert_eq!(bridge.locked_balance(token).to_u128(), Some(60));
+        }
+
+        #[ink::test]
+        fn bridge_in_psp22_releases_from_custody() {
+            let (mut bridge, token) = new_bridge_with_psp22();
+            let (alice, bob) = (accounts().alice, accounts().bob);
+
+            mock::set_balance(PSP22_CONTRACT.into(), alice, 100);
+            mock::approve(PSP22_CONTRACT.into(), alice, CONTRACT.into(), 100);
+
+            assert!(bridge
+                .bridge_out(token.clone(), U256::from_u128(60), U256::from_u128(2))
+                .is_ok());
+            assert!(bridge
+                .bridge_in(
+                    token.clone(),
+                    U256::from_u128(20),
+                    account_hash(bob),
+                    U256::from_u128(2),
+                    0
+                )
+                .is_ok());
+
+            assert_eq!(mock::balance_of(PSP22_CONTRACT.into(), bob), 20);
+            assert_eq!(mock::balance_of(PSP22_CONTRACT.into(), CONTRACT.into()), 40);
+            assert_eq!(bridge.locked_balance(token).to_u128(), Some(40));
+        }
+
+        #[ink::test]
+        fn bridge_in_rejects_replayed_source_nonces() {
+            let (mut bridge, token) = new_bridge_with_psp22();
+            let (alice, bob) = (accounts().alice, accounts().bob);
+            let (chain, other_chain) = (U256::from_u128(2), U256::from_u128(3));
+            let amount = U256::from_u128(10);
+
+            mock::set_balance(PSP22_CONTRACT.into(), alice, 100);
+            mock::approve(PSP22_CONTRACT.into(), alice, CONTRACT.into(), 100);
+            ink_env::test::set_account_balance::<DefaultEnvironment>(CONTRACT.into(), 10_000_000);
+            ink_env::test::set_value_transferred::<DefaultEnvironment>(0x50_0000);
+
+            assert!(bridge
+                .bridge_out(token.clone(), U256::from_u128(60), chain)
+                .is_ok());
+            assert!(bridge
+                .bridge_out_native(chain, hash("0xRecipientOnTargetChain"))
+                .is_ok());
+            assert_eq!(
+                bridge.bridge_in(token.clone(), amount, account_hash(bob), chain, 7),
+                Ok(())
+            );
+            assert_eq!(
+                bridge.bridge_in(token.clone(), amount, account_hash(bob), chain, 7),
+                Err(BridgeContractError::AlreadyProcessed)
+            );
+            assert_eq!(
+                bridge.bridge_in_native(amount, account_hash(bob), chain, 7),
+                Err(BridgeContractError::AlreadyProcessed)
+            );
+            assert_eq!(
+                bridge.bridge_in(token.clone(), amount, account_hash(bob), other_chain, 7),
+                Ok(())
+            );
+
+            assert_eq!(mock::balance_of(PSP22_CONTRACT.into(), bob), 20);
+            assert_eq!(bridge.locked_balance(token).to_u128(), Some(40));
+            assert_eq!(native_locked(&bridge), Some(0x50_0000));
+        }
+
+        #[ink::test]
+        fn bridge_out_psp22_maps_token_errors() {
+            let (mut bridge, token) = new_bridge_with_psp22();
+            let alice = accounts().alice;
+
+            mock::set_balance(PSP22_CONTRACT.into(), alice, 100);
+
+            let result = bridge.bridge_out(token.clone(), U256::from_u128(60), U256::from_u128(2));
+
+            assert_eq!(result, Err(BridgeContractError::TokenInsufficientAllowance));
+            assert_eq!(bridge.locked_balance(token).to_u128(), Some(0));
+        }
+
+        #[ink::test]
+        fn bridge_in_psp22_requires_owner() {
+            let (mut bridge, token) = new_bridge_with_psp22();
+            let bob = accounts().bob;
+
+            set_caller(bob);
+
+            let result = bridge.bridge_in(
+                token,
+                U256::from_u128(20),
+                account_hash(bob),
+                U256::from_u128(2),
+                0,
+            );
+
+            assert_eq!(result, Err(BridgeContractError::Unauthorized));
+        }
+
+        #[ink::test]
+        fn bridge_in_ledger_token_requires_relayer() {
+            let mut bridge = new_bridge();
+            let (bob, charlie) = (
+                account_hash(accounts().bob),
+                account_hash(accounts().charlie),
+            );
+            let (token, chain) = (hash("NATIVE_LEDGER_TOKEN"), U256::from_u128(2));
+
+            assert_eq!(
+                bridge.transfer(bob.clone(), token.clone(), U256::from_u128(10)),
+                Ok(())
+            );
+
+            set_caller(accounts().bob);
+            assert_eq!(
+                bridge.bridge_in(token.clone(), U256::from_u128(5), charlie.clone(), chain, 0),
+                Err(BridgeContractError::Unauthorized)
+            );
+            assert_eq!(bridge.balance_of(bob, token.clone()).to_u128(), Some(10));
+            assert_eq!(*bridge.processed_inbound_count, 0);
+
+            set_caller(accounts().alice);
+            assert_eq!(
+                bridge.bridge_in(token.clone(), U256::from_u128(5), charlie.clone(), chain, 0),
+                Ok(())
+            );
+            assert_eq!(bridge.balance_of(charlie, token).to_u128(), Some(5));
+        }
+
+        #[ink::test]
+        fn register_psp22_token_requires_owner() {
+            let mut bridge = new_bridge();
+
+            set_caller(accounts().bob);
+
+            let result = bridge.register_psp22_token(hash("PSP22_TOKEN"), PSP22_CONTRACT.into());
+
+            assert_eq!(result, Err(BridgeContractError::Unauthorized));
+        }
+
+        #[ink::test]
+        fn custody_supports_non_utf8_contract_accounts() {
+            let contract = AccountId::from([0xff; 32]);
+            let token = hash("NATIVE_LEDGER_TOKEN");
+            let custody = account_hash(contract);
+
+            ink_env::test::set_callee::<DefaultEnvironment>(contract);
+            set_caller(accounts().alice);
+
+            let mut bridge = Bridge::new(token.clone(), U256::from_u128(1_000), hash("CHER"));
+
+            assert!(bridge
+                .set_chain(
+                    U256::from_u128(2),
+                    Some(chain_config(0, 0, CherryMode::Lock))
+                )
+                .is_ok());
+            bridge.approve(custody.clone(), token.clone(), U256::from_u128(100));
+
+            assert!(bridge
+                .bridge_out(token.clone(), U256::from_u128(100), U256::from_u128(2))
+                .is_ok());
+            assert_eq!(
+                bridge.balance_of(custody, token.clone()).to_u128(),
+                Some(100)
+            );
+            assert_eq!(bridge.locked_balance(token).to_u128(), Some(100));
+        }
+
+        fn native_locked(bridge: &Bridge) -> Option<u128> {
+            bridge.locked_balance(hash(NATIVE_TOKEN)).to_u128()
+        }
+
+        #[ink::test]
+        fn bridge_out_native_locks_transferred_value() {
+            let mut bridge = new_bridge();
+
+            ink_env::test::set_value_transferred::<DefaultEnvironment>(5_000_000);
+
+            assert!(bridge
+                .bridge_out_native(U256::from_u128(2), hash("0xRecipientOnTargetChain"))
+                .is_ok());
+            assert_eq!(native_locked(&bridge), Some(5_000_000));
+        }
+
+        #[ink::test]
+        fn bridge_out_native_enforces_minimum_amount() {
+            let mut bridge = new_bridge();
+
+            assert!(bridge.set_min_native_amount(10_000_000).is_ok());
+            ink_env::test::set_value_transferred::<DefaultEnvironment>(5_000_000);
+
+            let result =
+                bridge.bridge_out_native(U256::from_u128(2), hash("0xRecipientOnTargetChain"));
+
+            assert_eq!(result, Err(BridgeContractError::BelowMinimumAmount));
+            assert_eq!(native_locked(&bridge), Some(0));
+        }
+
+        #[ink::test]
+        fn bridge_in_native_releases_locked_value() {
+            let mut bridge = new_bridge();
+            let bob = accounts().bob;
+            let bob_before = ink_env::test::get_account_balance::<DefaultEnvironment>(bob).unwrap();
+
+            ink_env::test::set_account_balance::<DefaultEnvironment>(CONTRACT.into(), 10_000_000);
+            ink_env::test::set_value_transferred::<DefaultEnvironment>(0x50_0000);
+
+            assert!(bridge
+                .bridge_out_native(U256::from_u128(2), hash("0xRecipientOnTargetChain"))
+                .is_ok());
+            assert!(bridge
+                .bridge_in_native(
+                    U256::from_u128(0x20_0000),
+                    account_hash(bob),
+                    U256::from_u128(2),
+                    0
+                )
+                .is_ok());
+
+            assert_eq!(
+                ink_env::test::get_account_balance::<DefaultEnvironment>(bob),
+                Ok(bob_before + 0x20_0000)
+            );
+            assert_eq!(native_locked(&bridge), Some(0x30_0000));
+        }
+
+        #[ink::test]
+        fn bridge_in_native_keeps_existential_deposit() {
+            let mut bridge = new_bridge();
+
+            ink_env::test::set_account_balance::<DefaultEnvironment>(CONTRACT.into(), 5_000_000);
+            ink_env::test::set_value_transferred::<DefaultEnvironment>(5_000_000);
+
+            assert!(bridge
+                .bridge_out_native(U256::from_u128(2), hash("0xRecipientOnTargetChain"))
+                .is_ok());
+
+            let result = bridge.bridge_in_native(
+                U256::from_u128(4_500_000),
+                account_hash(accounts().bob),
+                U256::from_u128(2),
+                0,
+            );
+
+            assert_eq!(result, Err(BridgeContractError::WouldReapAccount));
+            assert_eq!(native_locked(&bridge), Some(5_000_000));
+        }
+
+        #[ink::test]
+        fn ledger_queries_report_initial_supply() {
+            let bridge = new_bridge();
+            let token = hash("NATIVE_LEDGER_TOKEN");
+
+            assert_eq!(
+                bridge
+                    .balance_of(account_hash(accounts().alice), token.clone())
+                    .to_u128(),
+                Some(1_000)
+            );
+            assert_eq!(
+                bridge
+                    .balance_of(account_hash(accounts().bob), token.clone())
+                    .to_u128(),
+                Some(0)
+            );
+            assert_eq!(bridge.total_supply(token).to_u128(), Some(1_000));
+        }
+
+        #[ink::test]
+        fn increase_and_decrease_allowance() {
+            let mut bridge = new_bridge();
+            let (alice, bob) = (account_hash(accounts().alice), account_hash(accounts().bob));
+            let token = hash("NATIVE_LEDGER_TOKEN");
+
+            assert!(bridge
+                .increase_allowance(bob.clone(), token.clone(), U256::from_u128(50))
+                .is_ok());
+            assert!(bridge
+                .decrease_allowance(bob.clone(), token.clone(), U256::from_u128(20))
+                .is_ok());
+            assert_eq!(
+                bridge
+                    .allowance(alice.clone(), bob.clone(), token.clone())
+                    .to_u128(),
+                Some(30)
+            );
+
+            let result = bridge.decrease_allowance(bob.clone(), token.clone(), U256::from_u128(31));
+
+            assert_eq!(result, Err(BridgeContractError::InsufficientAllowance));
+            assert_eq!(bridge.allowance(alice, bob, token).to_u128(), Some(30));
+        }
+
+        #[ink::test]
+        fn increase_allowance_saturates_at_infinite() {
+            let mut bridge = new_bridge();
+            let (alice, bob) = (account_hash(accounts().alice), account_hash(accounts().bob));
+            let token = hash("NATIVE_LEDGER_TOKEN");
+
+            bridge.approve(bob.clone(), token.clone(), U256::new_ff());
+            assert_eq!(
+                bridge.increase_allowance(bob.clone(), token.clone(), U256::from_u128(1)),
+                Ok(())
+            );
+            assert_eq!(
+                bridge.allowance(alice.clone(), bob.clone(), token.clone()),
+                U256::new_ff()
+            );
+
+            bridge.approve(bob.clone(), token.clone(), U256::from_u128(10));
+
+            let large = U256::subtract_b_from_a(&U256::new_ff(), &U256::from_u128(5));
+
+            assert_eq!(
+                bridge.increase_allowance(bob.clone(), token.clone(), large),
+                Ok(())
+            );
+            assert_eq!(bridge.allowance(alice, bob, token), U256::new_ff());
+        }
+
+        #[ink::test]
+        fn transfer_from_deducts_spender_allowance_monotonically() {
+            let mut bridge = new_bridge();
+            let (alice, bob, charlie) = (
+                account_hash(accounts().alice),
+                account_hash(accounts().bob),
+                account_hash(accounts().charlie),
+            );
+            let token = hash("NATIVE_LEDGER_TOKEN");
+
+            bridge.approve(bob.clone(), token.clone(), U256::from_u128(50));
+            set_caller(accounts().bob);
+
+            let mut previous = 50;
+
+            for value in [10, 20] {
+                assert_eq!(
+                    bridge.transfer_from(
+                        alice.clone(),
+                        charlie.clone(),
+                        token.clone(),
+                        U256::from_u128(value)
+                    ),
+                    Ok(())
+                );
+
+                let current = bridge
+                    .allowance(alice.clone(), bob.clone(), token.clone())
+                    .to_u128()
+                    .unwrap();
+
+                assert_eq!(current, previous - value);
+                previous = current;
+            }
+
+            assert_eq!(
+                bridge.transfer_from(
+                    alice.clone(),
+                    charlie.clone(),
+                    token.clone(),
+                    U256::from_u128(21)
+                ),
+                Err(BridgeContractError::InsufficientAllowance)
+            );
+            assert_eq!(
+                bridge
+                    .allowance(alice.clone(), bob.clone(), token.clone())
+                    .to_u128(),
+                Some(20)
+            );
+            assert_eq!(
+                bridge
+                    .allowance(alice.clone(), charlie.clone(), token.clone())
+                    .to_u128(),
+                Some(0)
+            );
+            assert_eq!(bridge.allowance_count(alice), 1);
+            assert_eq!(bridge.balance_of(charlie, token).to_u128(), Some(30));
+        }
+
+        #[ink::test]
+        fn transfer_from_keeps_allowance_when_transfer_fails() {
+            let mut bridge = new_bridge();
+            let (alice, bob) = (account_hash(accounts().alice), account_hash(accounts().bob));
+            let token = hash("NATIVE_LEDGER_TOKEN");
+
+            bridge.approve(bob.clone(), token.clone(), U256::from_u128(50));
+            assert!(bridge
+                .transfer(bob.clone(), token.clone(), U256::from_u128(1_000))
+                .is_ok());
+            set_caller(accounts().bob);
+
+            assert_eq!(
+                bridge.transfer_from(
+                    alice.clone(),
+                    bob.clone(),
+                    token.clone(),
+                    U256::from_u128(10)
+                ),
+                Err(BridgeContractError::InsufficientBalance)
+            );
+            assert_eq!(bridge.allowance(alice, bob, token).to_u128(), Some(50));
+        }
+
+        #[ink::test]
+        fn infinite_allowance_is_never_decremented() {
+            let mut bridge = new_bridge();
+            let (alice, bob) = (account_hash(accounts().alice), account_hash(accounts().bob));
+            let token = hash("NATIVE_LEDGER_TOKEN");
+
+            bridge.approve(bob.clone(), token.clone(), U256::new_ff());
+            set_caller(accounts().bob);
+
+            for _ in 0..3 {
+                assert_eq!(
+                    bridge.transfer_from(
+                        alice.clone(),
+                        bob.clone(),
+                        token.clone(),
+                        U256::from_u128(10)
+                    ),
+                    Ok(())
+                );
+            }
+
+            assert_eq!(
+                bridge.allowance(alice, bob.clone(), token.clone()),
+                U256::new_ff()
+            );
+            assert_eq!(bridge.balance_of(bob, token).to_u128(), Some(30));
+        }
+
+        fn ecdsa_signer(seed: u8) -> (secp256k1::SecretKey, AccountId) {
+            let secret = secp256k1::SecretKey::from_slice(&[seed; 32]).unwrap();
+            let public = secp256k1::PublicKey::from_secret_key(secp256k1::SECP256K1, &secret);
+            let mut account = [0u8; 32];
+
+            ink_env::hash_bytes::<ink_env::hash::Blake2x256>(&public.serialize(), &mut account);
+
+            (secret, account.into())
+        }
+
+        fn ecdsa_sign(secret: &secp256k1::SecretKey, digest: [u8; 32]) -> [u8; 65] {
+            let message = secp256k1::Message::from_slice(&digest).unwrap();
+            let (recovery_id, compact) = secp256k1::SECP256K1
+                .sign_ecdsa_recoverable(&message, secret)
+                .serialize_compact();
+            let mut signature = [0u8; 65];
+
+            signature[..64].copy_from_slice(&compact);
+            signature[64] = recovery_id.to_i32() as u8;
+
+            signature
+        }
+
+        #[ink::test]
+        fn permit_sets_allowance_from_owner_signature() {
+            let mut bridge = new_bridge();
+            let (secret, owner) = ecdsa_signer(0x11);
+            let bob = account_hash(accounts().bob);
+            let token = hash("NATIVE_LEDGER_TOKEN");
+            let value = U256::from_u128(40);
+
+            assert!(bridge
+                .transfer(account_hash(owner), token.clone(), U256::from_u128(100))
+                .is_ok());
+
+            let digest = bridge.permit_digest(owner, bob.clone(), token.clone(), value, 1_000);
+            let signature = ecdsa_sign(&secret, digest);
+
+            set_caller(accounts().bob);
+            assert_eq!(
+                bridge.permit(owner, bob.clone(), token.clone(), value, 1_000, signature),
+                Ok(())
+            );
+            assert_eq!(bridge.permit_nonce(owner), 1);
+            assert_eq!(
+                bridge.permit(owner, bob.clone(), token.clone(), value, 1_000, signature),
+                Err(BridgeContractError::InvalidSignature)
+            );
+            assert_eq!(
+                bridge.transfer_from(account_hash(owner), bob.clone(), token.clone(), value),
+                Ok(())
+            );
+            assert_eq!(bridge.balance_of(bob, token).to_u128(), Some(40));
+        }
+
+        #[ink::test]
+        fn permit_rejects_wrong_signer_and_expired_deadline() {
+            let mut bridge = new_bridge();
+            let (_, owner) = ecdsa_signer(0x11);
+            let (other, _) = ecdsa_signer(0x22);
+            let bob = account_hash(accounts().bob);
+            let token = hash("NATIVE_LEDGER_TOKEN");
+            let value = U256::from_u128(40);
+
+            let digest = bridge.permit_digest(owner, bob.clone(), token.clone(), value, 10);
+
+            assert_eq!(
+                bridge.permit(
+                    owner,
+                    bob.clone(),
+                    token.clone(),
+                    value,
+                    10,
+                    ecdsa_sign(&other, digest)
+                ),
+                Err(BridgeContractError::InvalidSignature)
+            );
+
+            ink_env::test::advance_block::<DefaultEnvironment>();
+            ink_env::test::advance_block::<DefaultEnvironment>();
+
+            assert_eq!(
+                bridge.permit(owner, bob, token, value, 10, ecdsa_sign(&other, digest)),
+                Err(BridgeContractError::Expired)
+            );
+            assert_eq!(bridge.permit_nonce(owner), 0);
+        }
+
+        fn signed_outbound(
+            signer: AccountId,
+            token: &MultiChainAddrHash,
+            nonce: u64,
+            max_fee: u128,
+        ) -> SignedOutboundRequest {
+            SignedOutboundRequest {
+                signer,
+                request: outbound(token, 60),
+                nonce,
+                deadline: 1_000,
+                max_fee: U256::from_u128(max_fee),
+            }
+        }
+
+        /// A bridge where the ECDSA signer holds 100 ledger tokens and Bob
+        /// relays, before the signer has approved anything.
+        fn new_bridge_with_unapproved_signer() -> (Bridge, secp256k1::SecretKey, AccountId) {
+            let mut bridge = new_bridge();
+            let (secret, signer) = ecdsa_signer(0x11);
+
+            assert!(bridge
+                .transfer(
+                    account_hash(signer),
+                    hash("NATIVE_LEDGER_TOKEN"),
+                    U256::from_u128(100)
+                )
+                .is_ok());
+            assert_eq!(
+                bridge.set_chain(
+                    U256::from_u128(2),
+                    Some(chain_config(1_000, 500, CherryMode::Lock))
+                ),
+                Ok(())
+            );
+            assert_eq!(bridge.set_relay_fee_bps(500), Ok(()));
+            assert_eq!(bridge.set_relayer(accounts().bob, true), Ok(()));
+
+            (bridge, secret, signer)
+        }
+
+        fn new_bridge_with_signer() -> (Bridge, secp256k1::SecretKey, AccountId) {
+            let (mut bridge, secret, signer) = new_bridge_with_unapproved_signer();
+            let token = hash("NATIVE_LEDGER_TOKEN");
+            let custody = account_hash(CONTRACT.into());
+            let value = U256::from_u128(100);
+            let digest = bridge.permit_digest(signer, custody.clone(), token.clone(), value, 1_000);
+
+            assert_eq!(
+                bridge.permit(
+                    signer,
+                    custody,
+                    token,
+                    value,
+                    1_000,
+                    ecdsa_sign(&secret, digest)
+                ),
+                Ok(())
+            );
+
+            (bridge, secret, signer)
+        }
+
+        #[ink::test]
+        fn bridge_out_signed_reimburses_relayer() {
+            let (mut bridge, secret, signer) = new_bridge_with_signer();
+            let token = hash("NATIVE_LEDGER_TOKEN");
+            let request = signed_outbound(signer, &token, 1, 8);
+            let signature = ecdsa_sign(&secret, bridge.bridge_out_digest(request.clone()));
+
+            set_caller(accounts().bob);
+            assert_eq!(bridge.bridge_out_signed(request.clone(), signature), Ok(()));
+            assert_eq!(
+                bridge.bridge_out_signed(request, signature),
+                Err(BridgeContractError::InvalidNonce)
+            );
+
+            assert_eq!(
+                bridge
+                    .balance_of(account_hash(signer), token.clone())
+                    .to_u128(),
+                Some(40)
+            );
+            assert_eq!(
+                bridge
+                    .balance_of(account_hash(accounts().bob), token.clone())
+                    .to_u128(),
+                Some(2)
+            );
+            assert_eq!(bridge.locked_balance(token.clone()).to_u128(), Some(52));
+            assert_eq!(bridge.collected_fees(token).to_u128(), Some(6));
+            assert_eq!(
+                bridge.outbound_message(0).unwrap().sender,
+                account_hash(signer)
+            );
+            assert_eq!(bridge.permit_nonce(signer), 2);
+        }
+
+        #[ink::test]
+        fn bridge_out_with_permit_approves_and_bridges_in_one_call() {
+            let (mut bridge, secret, signer) = new_bridge_with_unapproved_signer();
+            let token = hash("NATIVE_LEDGER_TOKEN");
+            let custody = account_hash(CONTRACT.into());
+            let permit = Permit {
+                owner: signer,
+                spender: custody.clone(),
+                token: token.clone(),
+                value: U256::from_u128(60),
+                deadline: 1_000,
+            };
+            let permit_signature = ecdsa_sign(
+                &secret,
+                bridge.permit_digest(
+                    signer,
+                    custody.clone(),
+                    token.clone(),
+                    permit.value,
+                    permit.deadline,
+                ),
+            );
+            let request = signed_outbound(signer, &token, 1, 8);
+            let signature = ecdsa_sign(&secret, bridge.bridge_out_digest(request.clone()));
+
+            set_caller(accounts().bob);
+            assert_eq!(
+                bridge.bridge_out_signed(request.clone(), signature),
+                Err(BridgeContractError::InvalidNonce)
+            );
+            assert_eq!(
+                bridge.bridge_out_with_permit(permit, permit_signature, request, signature),
+                Ok(())
+            );
+
+            assert_eq!(
+                bridge
+                    .balance_of(account_hash(signer), token.clone())
+                    .to_u128(),
+                Some(40)
+            );
+            assert_eq!(
+                bridge
+                    .allowance(account_hash(signer), custody, token.clone())
+                    .to_u128(),
+                Some(0)
+            );
+            assert_eq!(bridge.locked_balance(token).to_u128(), Some(52));
+            assert_eq!(bridge.permit_nonce(signer), 2);
+        }
+
+        #[ink::test]
+        fn bridge_out_signed_enforces_max_fee_and_relayer() {
+            let (mut bridge, secret, signer) = new_bridge_with_signer();
+            let token = hash("NATIVE_LEDGER_TOKEN");
+            let request = signed_outbound(signer, &token, 1, 7);
+            let signature = ecdsa_sign(&secret, bridge.bridge_out_digest(request.clone()));
+
+            set_caller(accounts().charlie);
+            assert_eq!(
+                bridge.bridge_out_signed(request.clone(), signature),
+                Err(BridgeContractError::Unauthorized)
+            );
+
+            set_caller(accounts().bob);
+            assert_eq!(
+                bridge.bridge_out_signed(request.clone(), signature),
+                Err(BridgeContractError::FeeTooHigh)
+            );
+
+            let tampered = signed_outbound(signer, &token, 1, 8);
+
+            assert_eq!(
+                bridge.bridge_out_signed(tampered, signature),
+                Err(BridgeContractError::InvalidSignature)
+            );
+            assert_eq!(bridge.permit_nonce(signer), 1);
+        }
+
+        #[ink::test]
+        fn balance_of_batch_reports_each_pair() {
+            let bridge = new_bridge();
+            let token = hash("NATIVE_LEDGER_TOKEN");
+
+            let balances = bridge.balance_of_batch(vec![
+                (account_hash(accounts().alice), token.clone()),
+                (account_hash(accounts().bob), token),
+            ]);
+
+            assert_eq!(
+                balances.iter().map(|b| b.to_u128()).collect::<Vec<_>>(),
+                vec![Some(1_000), Some(0)]
+            );
+        }
+
+        #[ink::test]
+        fn set_approval_for_all_toggles_operator() {
+            let mut bridge = new_bridge();
+            let (alice, bob) = (account_hash(accounts().alice), account_hash(accounts().bob));
+
+            assert!(!bridge.is_approved_for_all(alice.clone(), bob.clone()));
+
+            bridge.set_approval_for_all(bob.clone(), true);
+            assert!(bridge.is_approved_for_all(alice.clone(), bob.clone()));
+
+            bridge.set_approval_for_all(bob.clone(), false);
+            assert!(!bridge.is_approved_for_all(alice, bob));
+        }
+
+        fn outbound(token: &MultiChainAddrHash, amount: u128) -> OutboundRequest {
+            OutboundRequest {
+                token_address: token.clone(),
+                token_amount: U256::from_u128(amount),
+                target_chain: U256::from_u128(2),
+            }
+        }
+
+        #[ink::test]
+        fn bridge_out_batch_best_effort_reports_each_item() {
+            let (mut bridge, token) = new_bridge_with_psp22();
+            let alice = accounts().alice;
+
+            mock::set_balance(PSP22_CONTRACT.into(), alice, 100);
+            mock::approve(PSP22_CONTRACT.into(), alice, CONTRACT.into(), 50);
+
+            let results = bridge
+                .bridge_out_batch(
+                    vec![
+                        outbound(&token, 30),
+                        outbound(&token, 30),
+                        outbound(&token, 20),
+                    ],
+                    BatchMode::BestEffort,
+                )
+                .unwrap_or_default();
+
+            assert_eq!(
+                results.iter().map(|r| r.is_ok()).collect::<Vec<_>>(),
+                vec![true, false, true]
+            );
+            assert_eq!(bridge.locked_balance(token).to_u128(), Some(50));
+        }
+
+        #[ink::test]
+        fn bridge_out_batch_all_or_nothing_fails_on_first_error() {
+            let (mut bridge, token) = new_bridge_with_psp22();
+            let alice = accounts().alice;
+
+            mock::set_balance(PSP22_CONTRACT.into(), alice, 100);
+            mock::approve(PSP22_CONTRACT.into(), alice, CONTRACT.into(), 50);
+
+            let result = bridge.bridge_out_batch(
+                vec![outbound(&token, 30), outbound(&token, 30)],
+                BatchMode::AllOrNothing,
+            );
+
+            assert_eq!(result, Err(BridgeContractError::TokenInsufficientAllowance));
+        }
+
+        #[ink::test]
+        fn bridge_in_batch_releases_each_message() {
+            let (mut bridge, token) = new_bridge_with_psp22();
+            let (alice, bob, charlie) = (accounts().alice, accounts().bob, accounts().charlie);
+
+            mock::set_balance(PSP22_CONTRACT.into(), alice, 100);
+            mock::approve(PSP22_CONTRACT.into(), alice, CONTRACT.into(), 100);
+
+            assert!(bridge
+                .bridge_out(token.clone(), U256::from_u128(60), U256::from_u128(2))
+                .is_ok());
+
+            let inbound = |recipient: AccountId, amount: u128, nonce: u64| InboundMessage {
+                token_address: token.clone(),
+                token_amount: U256::from_u128(amount),
+                recipient: account_hash(recipient),
+                from_chain: U256::from_u128(2),
+                source_nonce: nonce,
+            };
+
+            let results = bridge
+                .bridge_in_batch(
+                    vec![inbound(bob, 20, 0), inbound(charlie, 30, 1)],
+                    BatchMode::AllOrNothing,
+                )
+                .unwrap_or_default();
+
+            assert_eq!(results.len(), 2);
+            assert_eq!(mock::balance_of(PSP22_CONTRACT.into(), bob), 20);
+            assert_eq!(mock::balance_of(PSP22_CONTRACT.into(), charlie), 30);
+            assert_eq!(bridge.locked_balance(token).to_u128(), Some(10));
+        }
+
+        fn bridge_out_from_bob(bridge: &mut Bridge, token: &MultiChainAddrHash, amount: u128) {
+            let bob = accounts().bob;
+
+            mock::set_balance(PSP22_CONTRACT.into(), bob, 100);
+            mock::approve(PSP22_CONTRACT.into(), bob, CONTRACT.into(), 100);
+
+            set_caller(bob);
+            assert!(bridge
+                .bridge_out(token.clone(), U256::from_u128(amount), U256::from_u128(2))
+                .is_ok());
+        }
+
+        #[ink::test]
+        fn refund_returns_failed_outbound_minus_fee() {
+            let (mut bridge, token) = new_bridge_with_psp22();
+            let (alice, bob) = (accounts().alice, accounts().bob);
+
+            bridge_out_from_bob(&mut bridge, &token, 60);
+
+            set_caller(bob);
+            assert_eq!(bridge.refund(0), Err(BridgeContractError::NotRefundable));
+            assert_eq!(
+                bridge.mark_failed(0),
+                Err(BridgeContractError::Unauthorized)
+            );
+
+            set_caller(alice);
+            assert!(bridge.set_refund_fee_bps(1_000).is_ok());
+            assert!(bridge.mark_failed(0).is_ok());
+
+            set_caller(bob);
+            assert!(bridge.refund(0).is_ok());
+
+            assert_eq!(mock::balance_of(PSP22_CONTRACT.into(), bob), 94);
+            assert_eq!(mock::balance_of(PSP22_CONTRACT.into(), CONTRACT.into()), 6);
+            assert_eq!(bridge.locked_balance(token.clone()).to_u128(), Some(0));
+            assert_eq!(bridge.collected_fees(token).to_u128(), Some(6));
+            assert_eq!(
+                bridge.outbound_message(0).map(|m| m.status),
+                Some(OutboundStatus::Refunded)
+            );
+            assert_eq!(bridge.refund(0), Err(BridgeContractError::NotRefundable));
+        }
+
+        #[ink::test]
+        fn native_refund_keeps_fee_below_existential_deposit_in_custody() {
+            let mut bridge = new_bridge();
+            let (alice, bob) = (accounts().alice, accounts().bob);
+            let bob_before = ink_env::test::get_account_balance::<DefaultEnvironment>(bob).unwrap();
+
+            assert!(bridge.set_refund_fee_bps(100).is_ok());
+            ink_env::test::set_account_balance::<DefaultEnvironment>(CONTRACT.into(), 10_000_000);
+            ink_env::test::set_value_transferred::<DefaultEnvironment>(5_000_000);
+
+            set_caller(bob);
+            assert!(bridge
+                .bridge_out_native(U256::from_u128(2), hash("0xRecipientOnTargetChain"))
+                .is_ok());
+
+            set_caller(alice);
+            assert!(bridge.mark_failed(0).is_ok());
+
+            set_caller(bob);
+            assert!(bridge.refund(0).is_ok());
+
+            assert_eq!(
+                ink_env::test::get_account_balance::<DefaultEnvironment>(bob),
+                Ok(bob_before + 4_950_000)
+            );
+            assert_eq!(native_locked(&bridge), Some(0));
+            assert_eq!(
+                bridge.collected_fees(hash(NATIVE_TOKEN)).to_u128(),
+                Some(50_000)
+            );
+        }
+
+        #[ink::test]
+        fn refund_requires_failure_even_after_expiry() {
+            let (mut bridge, token) = new_bridge_with_psp22();
+
+            assert!(bridge.set_outbound_ttl(10).is_ok());
+            bridge_out_from_bob(&mut bridge, &token, 60);
+
+            ink_env::test::advance_block::<DefaultEnvironment>();
+            ink_env::test::advance_block::<DefaultEnvironment>();
+
+            assert_eq!(bridge.refund(0), Err(BridgeContractError::NotRefundable));
+
+            set_caller(accounts().alice);
+            assert!(bridge.mark_failed(0).is_ok());
+
+            set_caller(accounts().bob);
+            assert!(bridge.refund(0).is_ok());
+            assert_eq!(mock::balance_of(PSP22_CONTRACT.into(), accounts().bob), 100);
+        }
+
+        #[ink::test]
+        fn delivered_outbound_cannot_be_refunded() {
+            let (mut bridge, token) = new_bridge_with_psp22();
+
+            assert!(bridge.set_outbound_ttl(10).is_ok());
+            bridge_out_from_bob(&mut bridge, &token, 60);
+
+            set_caller(accounts().alice);
+            assert!(bridge.confirm_delivered(0).is_ok());
+
+            ink_env::test::advance_block::<DefaultEnvironment>();
+            ink_env::test::advance_block::<DefaultEnvironment>();
+
+            assert!(bridge.refund(0).is_err());
+            assert!(bridge.mark_failed(0).is_err());
+        }
+
+        #[ink::test]
+        fn error_codes_are_stable() {
+            assert_eq!(BridgeContractError::InsufficientBalance.code(), 1);
+            assert_eq!(BridgeContractError::Paused.code(), 6);
+            assert_eq!(BridgeContractError::Unauthorized.code(), 7);
+            assert_eq!(BridgeContractError::NotRefundable.code(), 21);
+            assert_eq!(
+                scale::Encode::encode(&BridgeContractError::InvalidAmount),
+                vec![5]
+            );
+        }
+
+        #[ink::test]
+        fn bridging_rejects_zero_amounts() {
+            let (mut bridge, token) = new_bridge_with_psp22();
+            let (zero, chain) = (U256::new_zero(), U256::from_u128(2));
+            let bob = account_hash(accounts().bob);
+
+            assert_eq!(
+                bridge.bridge_out(token.clone(), zero, chain),
+                Err(BridgeContractError::InvalidAmount)
+            );
+            assert_eq!(
+                bridge.bridge_in(token, zero, bob.clone(), chain, 0),
+                Err(BridgeContractError::InvalidAmount)
+            );
+            assert_eq!(
+                bridge.bridge_in_native(zero, bob, chain, 0),
+                Err(BridgeContractError::InvalidAmount)
+            );
+            assert!(bridge.outbound_message(0).is_none());
+        }
+
+        #[ink::test]
+        fn paused_bridge_rejects_bridging() {
+            let (mut bridge, token) = new_bridge_with_psp22();
+
+            set_caller(accounts().bob);
+            assert_eq!(
+                bridge.set_paused(true),
+                Err(BridgeContractError::Unauthorized)
+            );
+
+            set_caller(accounts().alice);
+            assert!(bridge.set_paused(true).is_ok());
+            assert_eq!(
+                bridge.bridge_out(token.clone(), U256::from_u128(1), U256::from_u128(2)),
+                Err(BridgeContractError::Paused)
+            );
+            assert_eq!(bridge.refund(0), Err(BridgeContractError::Paused));
+
+            assert!(bridge.set_paused(false).is_ok());
+            assert_eq!(
+                bridge.bridge_out(token, U256::from_u128(1), U256::from_u128(2)),
+                Err(BridgeContractError::TokenInsufficientAllowance)
+            );
+        }
+
+        fn ledger_balance(bridge: &Bridge, owner: AccountId) -> Option<u128> {
+            bridge
+                .balance_of(account_hash(owner), hash("NATIVE_LEDGER_TOKEN"))
+                .to_u128()
+        }
+
+        #[ink::test]
+        fn transfer_to_first_time_recipient() {
+            let mut bridge = new_bridge();
+            let (alice, bob) = (accounts().alice, accounts().bob);
+
+            assert_eq!(ledger_balance(&bridge, bob), Some(0));
+            assert_eq!(
+                bridge.transfer(
+                    account_hash(bob),
+                    hash("NATIVE_LEDGER_TOKEN"),
+                    U256::from_u128(100)
+                ),
+                Ok(())
+            );
+
+            assert_eq!(ledger_balance(&bridge, alice), Some(900));
+            assert_eq!(ledger_balance(&bridge, bob), Some(100));
+        }
+
+        #[ink::test]
+        fn self_transfer_keeps_balance() {
+            let mut bridge = new_bridge();
+            let alice = accounts().alice;
+
+            assert_eq!(
+                bridge.transfer(
+                    account_hash(alice),
+                    hash("NATIVE_LEDGER_TOKEN"),
+                    U256::from_u128(100)
+                ),
+                Ok(())
+            );
+
+            assert_eq!(ledger_balance(&bridge, alice), Some(1_000));
+        }
+
+        #[ink::test]
+        fn zero_amount_transfer_is_a_no_op() {
+            let mut bridge = new_bridge();
+            let (alice, bob) = (accounts().alice, accounts().bob);
+            let token = hash("NATIVE_LEDGER_TOKEN");
+
+            assert_eq!(
+                bridge.transfer(account_hash(bob), token.clone(), U256::new_zero()),
+                Ok(())
+            );
+            assert_eq!(
+                bridge.transfer(account_hash(bob), hash("SPAM"), U256::new_zero()),
+                Err(BridgeContractError::UnknownToken)
+            );
+
+            assert_eq!(ledger_balance(&bridge, alice), Some(1_000));
+            assert_eq!(ledger_balance(&bridge, bob), Some(0));
+            assert_eq!(bridge.token_count(), 1);
+            assert_eq!(bridge.holder_count(token), 1);
+            assert_eq!(bridge.holder_count(hash("SPAM")), 0);
+        }
+
+        #[ink::test]
+        fn transfer_from_empty_balance_fails() {
+            let mut bridge = new_bridge();
+            let (alice, bob) = (accounts().alice, accounts().bob);
+
+            set_caller(bob);
+            assert_eq!(
+                bridge.transfer(
+                    account_hash(alice),
+                    hash("NATIVE_LEDGER_TOKEN"),
+                    U256::from_u128(1)
+                ),
+                Err(BridgeContractError::InsufficientBalance)
+            );
+
+            assert_eq!(ledger_balance(&bridge, alice), Some(1_000));
+            assert_eq!(ledger_balance(&bridge, bob), Some(0));
+        }
+
+        #[ink::test]
+        fn missing_balances_read_as_zero() {
+            let bridge = new_bridge();
+
+            assert_eq!(
+                bridge
+                    .get_balance_of("UNKNOWN_TOKEN".to_string(), "UNKNOWN_OWNER".to_string())
+                    .to_u128(),
+                Some(0)
+            );
+            assert_eq!(
+                bridge
+                    .balance_of(hash("UNKNOWN_OWNER"), hash("UNKNOWN_TOKEN"))
+                    .to_u128(),
+                Some(0)
+            );
+        }
+
+        #[ink::test]
+        fn transfer_batch_to_first_time_recipient() {
+            let mut bridge = new_bridge();
+            let (alice, bob) = (accounts().alice, accounts().bob);
+            let token = hash("NATIVE_LEDGER_TOKEN");
+
+            assert_eq!(
+                bridge.transfer_batch(
+                    account_hash(bob),
+                    vec![
+                        (token.clone(), U256::from_u128(100)),
+                        (token, U256::from_u128(50))
+                    ]
+                ),
+                Ok(())
+            );
+
+            assert_eq!(ledger_balance(&bridge, alice), Some(850));
+            assert_eq!(ledger_balance(&bridge, bob), Some(150));
+        }
+
+        #[ink::test]
+        fn audit_token_sums_indexed_holders() {
+            let mut bridge = new_bridge();
+            let token = hash("NATIVE_LEDGER_TOKEN");
+
+            assert!(bridge
+                .transfer(
+                    account_hash(accounts().bob),
+                    token.clone(),
+                    U256::from_u128(100)
+                )
+                .is_ok());
+            assert!(bridge
+                .transfer(
+                    account_hash(accounts().charlie),
+                    token.clone(),
+                    U256::from_u128(50)
+                )
+                .is_ok());
+
+            let audit = bridge.audit_token(token.clone(), 0, 10);
+
+            assert_eq!(audit.holders, 3);
+            assert_eq!(audit.sum_of_balances.to_u128(), Some(1_000));
+            assert_eq!(audit.total_supply.to_u128(), Some(1_000));
+            assert!(audit.consistent);
+
+            let first_page = bridge.audit_token(token.clone(), 0, 2);
+            let second_page = bridge.audit_token(token, 2, 2);
+
+            assert_eq!(first_page.sum_of_balances.to_u128(), Some(950));
+            assert_eq!(second_page.sum_of_balances.to_u128(), Some(50));
+            assert!(!first_page.consistent && !second_page.consistent);
+        }
+
+        #[ink::test]
+        fn indexes_list_tokens_and_paginate_holders() {
+            let mut bridge = new_bridge();
+            let token = hash("NATIVE_LEDGER_TOKEN");
+
+            for recipient in [accounts().bob, accounts().charlie, accounts().django] {
+                assert!(bridge
+                    .transfer(account_hash(recipient), token.clone(), U256::from_u128(10))
+                    .is_ok());
+            }
+
+            assert_eq!(bridge.token_count(), 1);
+            assert_eq!(bridge.tokens(0, 10), vec![token.clone()]);
+            assert_eq!(bridge.holder_count(token.clone()), 4);
+
+            let first_page = bridge.holders_of(token.clone(), 0, 2);
+            let second_page = bridge.holders_of(token.clone(), 2, 2);
+
+            assert_eq!(
+                first_page,
+                vec![
+                    (account_hash(accounts().alice), U256::from_u128(970)),
+                    (account_hash(accounts().bob), U256::from_u128(10)),
+                ]
+            );
+            assert_eq!(
+                second_page,
+                vec![
+                    (account_hash(accounts().charlie), U256::from_u128(10)),
+                    (account_hash(accounts().django), U256::from_u128(10)),
+                ]
+            );
+            assert!(bridge.holders_of(token, 4, 2).is_empty());
+        }
+
+        #[ink::test]
+        fn indexes_list_allowances_per_owner() {
+            let mut bridge = new_bridge();
+            let (alice, bob, charlie) = (
+                account_hash(accounts().alice),
+                account_hash(accounts().bob),
+                account_hash(accounts().charlie),
+            );
+            let token = hash("NATIVE_LEDGER_TOKEN");
+
+            bridge.approve(bob.clone(), token.clone(), U256::from_u128(5));
+            bridge.approve(charlie.clone(), token.clone(), U256::from_u128(7));
+            bridge.approve(bob.clone(), token.clone(), U256::from_u128(9));
+
+            assert_eq!(bridge.allowance_count(alice.clone()), 2);
+            assert_eq!(
+                bridge.allowances_of(alice, 0, 10),
+                vec![
+                    (bob, token.clone(), U256::from_u128(9)),
+                    (charlie, token, U256::from_u128(7)),
+                ]
+            );
+        }
+
+        fn allocation(token: &str, supply: u128, recipient: AccountId) -> TokenAllocation {
+            TokenAllocation {
+                token: hash(token),
+                supply: U256::from_u128(supply),
+                recipient: account_hash(recipient),
+                metadata: TokenMetadata {
+                    name: token.to_string(),
+                    symbol: token.to_string(),
+                    decimals: 12,
+                },
+            }
+        }
+
+        #[ink::test]
+        fn new_with_allocations_credits_each_recipient() {
+            ink_env::test::set_callee::<DefaultEnvironment>(CONTRACT.into());
+            set_caller(accounts().alice);
+
+            let bridge = Bridge::new_with_allocations(
+                vec![
+                    allocation("CHER", 100, accounts().alice),
+                    allocation("CHER", 50, accounts().bob),
+                    allocation("USDT", 200, accounts().bob),
+                ],
+                hash("CHER"),
+            );
+
+            assert_eq!(
+                bridge.balance_of(account_hash(accounts().alice), hash("CHER")),
+                U256::from_u128(100)
+            );
+            assert_eq!(
+                bridge.balance_of(account_hash(accounts().bob), hash("CHER")),
+                U256::from_u128(50)
+            );
+            assert_eq!(bridge.total_supply(hash("CHER")), U256::from_u128(150));
+            assert_eq!(bridge.total_supply(hash("USDT")), U256::from_u128(200));
+            assert_eq!(bridge.token_count(), 2);
+            assert_eq!(
+                bridge.token_metadata(hash("USDT")).map(|m| m.decimals),
+                Some(12)
+            );
+            assert_eq!(ink_env::test::recorded_events().count(), 2);
+        }
+
+        #[ink::test]
+        fn create_token_is_owner_only_and_unique() {
+            let mut bridge = new_bridge();
+
+            set_caller(accounts().bob);
+            assert_eq!(
+                bridge.create_token(allocation("USDT", 200, accounts().bob)),
+                Err(BridgeContractError::Unauthorized)
+            );
+
+            set_caller(accounts().alice);
+            assert_eq!(
+                bridge.create_token(allocation("USDT", 200, accounts().bob)),
+                Ok(())
+            );
+            assert_eq!(
+                bridge.create_token(allocation("USDT", 200, accounts().bob)),
+                Err(BridgeContractError::TokenAlreadyExists)
+            );
+            assert_eq!(
+                bridge.balance_of(account_hash(accounts().bob), hash("USDT")),
+                U256::from_u128(200)
+            );
+        }
+
+        #[ink::test]
+        fn cherry_token_is_configurable_by_owner() {
+            let mut bridge = new_bridge();
+
+            assert_eq!(bridge.cherry_token(), hash("CHER"));
+
+            set_caller(accounts().bob);
+            assert_eq!(
+                bridge.set_cherry_token(hash("CHER_TESTNET")),
+                Err(BridgeContractError::Unauthorized)
+            );
+
+            set_caller(accounts().alice);
+            assert_eq!(bridge.set_cherry_token(hash("CHER_TESTNET")), Ok(()));
+            assert_eq!(bridge.cherry_token(), hash("CHER_TESTNET"));
+        }
+
+        fn chain_config(fee_bps: u32, cherry_fee_bps: u32, cherry_mode: CherryMode) -> ChainConfig {
+            ChainConfig {
+                fee_bps,
+                cherry_fee_bps,
+                cherry_mode,
+            }
+        }
+
+        fn new_bridge_with_cherry(cherry_mode: CherryMode) -> Bridge {
+            let mut bridge = new_bridge();
+
+            assert_eq!(
+                bridge.create_token(allocation("CHER", 200, accounts().alice)),
+                Ok(())
+            );
+            assert_eq!(
+                bridge.set_chain(
+                    U256::from_u128(2),
+                    Some(chain_config(1_000, 500, cherry_mode))
+                ),
+                Ok(())
+            );
+            bridge.approve(
+                account_hash(CONTRACT.into()),
+                hash("CHER"),
+                U256::from_u128(200),
+            );
+
+            bridge
+        }
+
+        #[ink::test]
+        fn set_chain_validates_fees() {
+            let mut bridge = new_bridge();
+
+            assert_eq!(
+                bridge.set_chain(
+                    U256::from_u128(3),
+                    Some(chain_config(100, 200, CherryMode::Lock))
+                ),
+                Err(BridgeContractError::InvalidFee)
+            );
+            assert_eq!(
+                bridge.set_chain(
+                    U256::from_u128(3),
+                    Some(chain_config(MAX_FEE_BPS + 1, 0, CherryMode::Lock))
+                ),
+                Err(BridgeContractError::InvalidFee)
+            );
+
+            set_caller(accounts().bob);
+            assert_eq!(
+                bridge.set_chain(
+                    U256::from_u128(3),
+                    Some(chain_config(100, 50, CherryMode::Lock))
+                ),
+                Err(BridgeContractError::Unauthorized)
+            );
+            assert_eq!(bridge.chain(U256::from_u128(3)), None);
+        }
+
+        #[ink::test]
+        fn bridge_out_requires_registered_chain() {
+            let (mut bridge, token) = new_bridge_with_psp22();
+            let alice = accounts().alice;
+
+            mock::set_balance(PSP22_CONTRACT.into(), alice, 100);
+            mock::approve(PSP22_CONTRACT.into(), alice, CONTRACT.into(), 100);
+            ink_env::test::set_value_transferred::<DefaultEnvironment>(5_000_000);
+
+            assert_eq!(
+                bridge.bridge_out(token.clone(), U256::from_u128(60), U256::from_u128(3)),
+                Err(BridgeContractError::UnknownChain)
+            );
+            assert_eq!(
+                bridge.bridge_out_native(U256::from_u128(3), hash("0xRecipientOnTargetChain")),
+                Err(BridgeContractError::UnknownChain)
+            );
+            assert_eq!(bridge.locked_balance(token).to_u128(), Some(0));
+            assert!(bridge.outbound_message(0).is_none());
+        }
+
+        #[ink::test]
+        fn bridge_out_charges_chain_fee() {
+            let (mut bridge, token) = new_bridge_with_psp22();
+            let alice = accounts().alice;
+
+            assert_eq!(
+                bridge.set_chain(
+                    U256::from_u128(2),
+                    Some(chain_config(1_000, 500, CherryMode::Lock))
+                ),
+                Ok(())
+            );
+            mock::set_balance(PSP22_CONTRACT.into(), alice, 100);
+            mock::approve(PSP22_CONTRACT.into(), alice, CONTRACT.into(), 100);
+
+            assert!(bridge
+                .bridge_out(token.clone(), U256::from_u128(60), U256::from_u128(2))
+                .is_ok());
+
+            assert_eq!(mock::balance_of(PSP22_CONTRACT.into(), CONTRACT.into()), 60);
+            assert_eq!(bridge.locked_balance(token.clone()).to_u128(), Some(54));
+            assert_eq!(bridge.collected_fees(token.clone()).to_u128(), Some(6));
+            assert_eq!(
+                bridge.outbound_message(0).unwrap().token_amount.to_u128(),
+                Some(54)
+            );
+
+            assert_eq!(
+                bridge.withdraw_fees(
+                    token.clone(),
+                    account_hash(accounts().bob),
+                    U256::from_u128(6)
+                ),
+                Ok(())
+            );
+            assert_eq!(mock::balance_of(PSP22_CONTRACT.into(), accounts().bob), 6);
+            assert_eq!(bridge.collected_fees(token).to_u128(), Some(0));
+        }
+
+        fn bridge_events() -> Vec<Event> {
+            ink_env::test::recorded_events()
+                .map(|e| <Event as scale::Decode>::decode(&mut &e.data[..]).unwrap())
+                .filter(|e| {
+                    matches!(
+                        e,
+                        Event::BridgedOut(_)
+                            | Event::BridgedIn(_)
+                            | Event::BridgeOutSimple(_)
+                            | Event::BridgeInSimple(_)
+                            | Event::BridgeCherrySimple(_)
+                    )
+                })
+                .collect()
+        }
+
+        #[ink::test]
+        fn bridge_out_emits_one_versioned_event() {
+            let (mut bridge, token) = new_bridge_with_psp22();
+            let alice = accounts().alice;
+
+            assert_eq!(
+                bridge.set_chain(
+                    U256::from_u128(2),
+                    Some(chain_config(1_000, 500, CherryMode::Lock))
+                ),
+                Ok(())
+            );
+            mock::set_balance(PSP22_CONTRACT.into(), alice, 100);
+            mock::approve(PSP22_CONTRACT.into(), alice, CONTRACT.into(), 100);
+
+            assert!(bridge
+                .bridge_out(token.clone(), U256::from_u128(60), U256::from_u128(2))
+                .is_ok());
+
+            let events = bridge_events();
+
+            assert_eq!(events.len(), 1);
+            match &events[0] {
+                Event::BridgedOut(e) => {
+                    assert_eq!(e.version, EVENT_SCHEMA_VERSION);
+                    assert_eq!(e.token, token.digest());
+                    assert_eq!(e.sender, account_hash(alice).digest());
+                    assert_eq!(e.nonce, 0);
+                    assert_eq!(e.amount.to_u128(), Some(54));
+                    assert_eq!(e.fee.to_u128(), Some(6));
+                    assert_eq!(e.route, BridgeRoute::Token);
+                }
+                _ => panic!("expected BridgedOut"),
+            }
+        }
+
+        #[ink::test]
+        fn legacy_mode_also_emits_string_events() {
+            let mut bridge = new_bridge();
+            let token = hash("NATIVE_LEDGER_TOKEN");
+
+            set_caller(accounts().bob);
+            assert_eq!(
+                bridge.set_legacy_events(true),
+                Err(BridgeContractError::Unauthorized)
+            );
+
+            set_caller(accounts().alice);
+            assert_eq!(bridge.set_legacy_events(true), Ok(()));
+            assert_eq!(
+                bridge.bridge_in_string(
+                    "NATIVE_LEDGER_TOKEN".to_string(),
+                    "25".to_string(),
+                    "BOB_ON_TARGET".to_string(),
+                    "2".to_string(),
+                    0
+                ),
+                Ok(())
+            );
+
+            let events = bridge_events();
+
+            assert_eq!(events.len(), 2);
+            match &events[0] {
+                Event::BridgeInSimple(e) => {
+                    assert_eq!(e.token_address, "NATIVE_LEDGER_TOKEN");
+                    assert_eq!(e.token_amount, "25");
+                    assert_eq!(e.recipient, "BOB_ON_TARGET");
+                }
+                _ => panic!("expected BridgeInSimple"),
+            }
+            match &events[1] {
+                Event::BridgedIn(e) => {
+                    assert_eq!(e.token_address, token);
+                    assert_eq!(e.recipient, hash("BOB_ON_TARGET").digest());
+                    assert_eq!(e.source_nonce, Some(0));
+                    assert_eq!(e.route, BridgeRoute::Token);
+                }
+                _ => panic!("expected BridgedIn"),
+            }
+        }
+
+        #[ink::test]
+        fn bridge_cherry_locks_with_discounted_fee() {
+            let mut bridge = new_bridge_with_cherry(CherryMode::Lock);
+            let alice = account_hash(accounts().alice);
+
+            assert_eq!(
+                bridge.bridge_cherry(U256::from_u128(100), U256::from_u128(2), hash("0xdead")),
+                Ok(())
+            );
+
+            assert_eq!(
+                bridge.balance_of(alice.clone(), hash("CHER")).to_u128(),
+                Some(100)
+            );
+            assert_eq!(bridge.locked_balance(hash("CHER")).to_u128(), Some(95));
+            assert_eq!(bridge.collected_fees(hash("CHER")).to_u128(), Some(5));
+            assert_eq!(bridge.total_supply(hash("CHER")).to_u128(), Some(200));
+
+            let message = bridge.outbound_message(0).unwrap();
+
+            assert_eq!(message.token_amount.to_u128(), Some(95));
+            assert!(!message.burned);
+        }
+
+        #[ink::test]
+        fn bridge_cherry_burns_when_chain_requires_it() {
+            let mut bridge = new_bridge_with_cherry(CherryMode::Burn);
+            let alice = account_hash(accounts().alice);
+
+            assert_eq!(
+                bridge.bridge_cherry(U256::from_u128(100), U256::from_u128(2), hash("0xdead")),
+                Ok(())
+            );
+
+            assert_eq!(
+                bridge.balance_of(alice.clone(), hash("CHER")).to_u128(),
+                Some(100)
+            );
+            assert_eq!(bridge.locked_balance(hash("CHER")).to_u128(), Some(0));
+            assert_eq!(bridge.collected_fees(hash("CHER")).to_u128(), Some(5));
+            assert_eq!(bridge.total_supply(hash("CHER")).to_u128(), Some(105));
+            assert!(bridge.outbound_message(0).unwrap().burned);
+        }
+
+        #[ink::test]
+        fn bridge_cherry_burns_without_approval_when_fee_is_zero() {
+            let mut bridge = new_bridge();
+            let alice = account_hash(accounts().alice);
+
+            assert_eq!(
+                bridge.create_token(allocation("CHER", 200, accounts().alice)),
+                Ok(())
+            );
+            assert_eq!(
+                bridge.set_chain(
+                    U256::from_u128(2),
+                    Some(chain_config(1_000, 0, CherryMode::Burn))
+                ),
+                Ok(())
+            );
+
+            assert_eq!(
+                bridge.bridge_cherry(U256::from_u128(100), U256::from_u128(2), hash("0xdead")),
+                Ok(())
+            );
+
+            assert_eq!(bridge.balance_of(alice, hash("CHER")).to_u128(), Some(100));
+            assert_eq!(bridge.collected_fees(hash("CHER")).to_u128(), Some(0));
+            assert_eq!(bridge.total_supply(hash("CHER")).to_u128(), Some(100));
+        }
+
+        #[ink::test]
+        fn split_fee_handles_amounts_above_u128() {
+            let value = U256::new_ff();
+
+            assert_eq!(Bridge::split_fee(&value, 0), Ok((value, U256::new_zero())));
+
+            let (net, fee) = Bridge::split_fee(&value, 500).unwrap();
+
+            assert!(fee.to_u128().is_none());
+            assert_eq!(U256::add_a_with_b(&net, &fee), value);
+        }
+
+        #[ink::test]
+        fn bridge_cherry_requires_registered_chain() {
+            let mut bridge = new_bridge_with_cherry(CherryMode::Lock);
+
+            assert_eq!(
+                bridge.bridge_cherry(U256::from_u128(100), U256::from_u128(3), hash("0xdead")),
+                Err(BridgeContractError::UnknownChain)
+            );
+        }
+
+        #[ink::test]
+        fn receive_cherry_mints_once_per_source_nonce() {
+            let mut bridge = new_bridge_with_cherry(CherryMode::Burn);
+            let bob = accounts().bob;
+
+            set_caller(bob);
+            assert_eq!(
+                bridge.receive_cherry(
+                    U256::from_u128(40),
+                    account_hash(bob),
+                    U256::from_u128(2),
+                    7
+                ),
+                Err(BridgeContractError::Unauthorized)
+            );
+
+            set_caller(accounts().alice);
+            assert_eq!(bridge.set_cherry_attester(bob, true), Ok(()));
+
+            set_caller(bob);
+            assert_eq!(
+                bridge.receive_cherry(
+                    U256::from_u128(40),
+                    account_hash(bob),
+                    U256::from_u128(2),
+                    7
+                ),
+                Ok(())
+            );
+            assert_eq!(
+                bridge.receive_cherry(
+                    U256::from_u128(40),
+                    account_hash(bob),
+                    U256::from_u128(2),
+                    7
+                ),
+                Err(BridgeContractError::AlreadyProcessed)
+            );
+            assert_eq!(
+                bridge
+                    .balance_of(account_hash(bob).clone(), hash("CHER"))
+                    .to_u128(),
+                Some(40)
+            );
+            assert_eq!(bridge.total_supply(hash("CHER")).to_u128(), Some(240));
+        }
+
+        #[ink::test]
+        fn receive_cherry_releases_locked_tokens() {
+            let mut bridge = new_bridge_with_cherry(CherryMode::Lock);
+            let bob = account_hash(accounts().bob);
+
+            assert_eq!(
+                bridge.bridge_cherry(U256::from_u128(100), U256::from_u128(2), hash("0xdead")),
+                Ok(())
+            );
+            assert_eq!(
+                bridge.receive_cherry(U256::from_u128(40), bob.clone(), U256::from_u128(2), 1),
+                Ok(())
+            );
+
+            assert_eq!(
+                bridge.balance_of(bob.clone(), hash("CHER")).to_u128(),
+                Some(40)
+            );
+            assert_eq!(bridge.locked_balance(hash("CHER")).to_u128(), Some(55));
+        }
+
+        #[ink::test]
+        fn upgrade_waits_for_the_timelock() {
+            let mut bridge = new_bridge();
+            let code_hash = Hash::from([0x42; 32]);
+
+            *bridge.upgrade_delay = 12;
+
+            assert_eq!(
+                bridge.upgrade(code_hash),
+                Err(BridgeContractError::NoPendingUpgrade)
+            );
+
+            set_caller(accounts().bob);
+            assert_eq!(
+                bridge.schedule_upgrade(code_hash),
+                Err(BridgeContractError::Unauthorized)
+            );
+
+            set_caller(accounts().alice);
+            assert_eq!(bridge.schedule_upgrade(code_hash), Ok(()));
+            assert_eq!(
+                bridge.pending_upgrade(),
+                Some(PendingUpgrade {
+                    code_hash,
+                    ready_at: 12
+                })
+            );
+            assert_eq!(
+                bridge.upgrade(Hash::from([0x43; 32])),
+                Err(BridgeContractError::NoPendingUpgrade)
+            );
+
+            ink_env::test::advance_block::<DefaultEnvironment>();
+
+            assert_eq!(
+                bridge.upgrade(code_hash),
+                Err(BridgeContractError::UpgradeTimelocked)
+            );
+            assert_eq!(bridge.cancel_upgrade(), Ok(()));
+            assert_eq!(bridge.pending_upgrade(), None);
+            assert_eq!(
+                bridge.cancel_upgrade(),
+                Err(BridgeContractError::NoPendingUpgrade)
+            );
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "does not support `set_code_hash`")]
+        fn upgrade_sets_the_code_hash_once_ready() {
+            let mut bridge = new_bridge();
+            let code_hash = Hash::from([0x42; 32]);
+
+            *bridge.upgrade_delay = 12;
+            assert_eq!(bridge.schedule_upgrade(code_hash), Ok(()));
+
+            ink_env::test::advance_block::<DefaultEnvironment>();
+            ink_env::test::advance_block::<DefaultEnvironment>();
+
+            let _ = bridge.upgrade(code_hash);
+        }
+
+        #[ink::test]
+        fn upgrade_delay_can_only_grow() {
+            let mut bridge = new_bridge();
+            let delay = bridge.upgrade_delay();
+
+            assert_eq!(
+                bridge.set_upgrade_delay(delay - 1),
+                Err(BridgeContractError::DelayTooShort)
+            );
+            assert_eq!(bridge.set_upgrade_delay(delay + 1), Ok(()));
+            assert_eq!(bridge.upgrade_delay(), delay + 1);
+        }
+
+        #[ink::test]
+        fn migrate_brings_storage_to_the_current_version() {
+            let mut bridge = new_bridge();
+
+            assert_eq!(bridge.storage_version(), STORAGE_VERSION);
+            assert_eq!(bridge.migrate(), Ok(STORAGE_VERSION));
+
+            *bridge.storage_version = 0;
+
+            set_caller(accounts().bob);
+            assert_eq!(bridge.migrate(), Err(BridgeContractError::Unauthorized));
+
+            set_caller(accounts().alice);
+            assert_eq!(bridge.migrate(), Ok(STORAGE_VERSION));
+            assert_eq!(bridge.storage_version(), STORAGE_VERSION);
+
+            let migrated = ink_env::test::recorded_events()
+                .map(|e| <Event as scale::Decode>::decode(&mut &e.data[..]).unwrap())
+                .filter(|e| matches!(e, Event::Migrated(_)))
+                .count();
+
+            assert_eq!(migrated, 1);
+        }
+
+        #[ink::test]
+        fn schedule_upgrade_rejects_overflowing_delay() {
+            let mut bridge = new_bridge();
+
+            ink_env::test::advance_block::<DefaultEnvironment>();
+
+            assert_eq!(bridge.set_upgrade_delay(Timestamp::MAX), Ok(()));
+            assert_eq!(
+                bridge.schedule_upgrade(Hash::from([0x42; 32])),
+                Err(BridgeContractError::DelayOverflow)
+            );
+            assert_eq!(bridge.pending_upgrade(), None);
+        }
+
+        /// Writes `bridge` to contract storage as code that lacks its last
+        /// `missing` fields would have, then loads it with the current layout.
+        fn reload_without_trailing_fields(bridge: &Bridge, missing: u64) -> Bridge {
+            use ink_storage::traits::{pull_spread_root, push_spread_root, KeyPtr, SpreadLayout};
+
+            let root = ink_primitives::Key::from([0x00; 32]);
+            let mut ptr = KeyPtr::from(root);
+
+            push_spread_root(bridge, &root);
+            ptr.advance_by(<Bridge as SpreadLayout>::FOOTPRINT - missing);
+
+            for _ in 0..missing {
+                ink_env::clear_contract_storage(ptr.advance_by(1));
+            }
+
+            pull_spread_root(&root)
+        }
+
+        #[ink::test]
+        fn migrate_loads_and_upgrades_unversioned_storage() {
+            let bridge = new_bridge_with_cherry(CherryMode::Lock);
+
+            // storage_version, upgrade_delay, pending_upgrade, allowance_owners,
+            // allowance_owner_count, processed_inbound_index,
+            // processed_inbound_count, state_imported, import_checksum,
+            // imported_chunks and expected_import_checksum came with version 1.
+            let mut bridge = reload_without_trailing_fields(&bridge, 11);
+
+            assert_eq!(bridge.storage_version(), 0);
+            assert_eq!(bridge.upgrade_delay(), 0);
+            assert_eq!(bridge.migrate(), Ok(STORAGE_VERSION));
+            assert_eq!(bridge.upgrade_delay(), DEFAULT_UPGRADE_DELAY);
+            assert_eq!(bridge.storage_version(), STORAGE_VERSION);
+        }
+
+        fn chunk_len(chunk: &[u8]) -> usize {
+            match <StateChunk as scale::Decode>::decode(&mut &chunk[..]).unwrap() {
+                StateChunk::Tokens(tokens) => tokens.len(),
+                StateChunk::Balances { balances, .. } => balances.len(),
+                StateChunk::Allowances { allowances, .. } => allowances.len(),
+                StateChunk::Outbound { messages, .. } => messages.len(),
+                StateChunk::Inbound(nonces) => nonces.len(),
+            }
+        }
+
+        fn export_all(bridge: &Bridge) -> Vec<Vec<u8>> {
+            let mut sections = vec![
+                StateSection::Tokens,
+                StateSection::Outbound,
+                StateSection::Inbound,
+            ];
+
+            sections.extend(
+                bridge
+                    .tokens(0, MAX_PAGE_SIZE)
+                    .into_iter()
+                    .map(StateSection::Balances),
+            );
+            sections.extend(
+                bridge
+                    .allowance_owners(0, MAX_PAGE_SIZE)
+                    .into_iter()
+                    .map(StateSection::Allowances),
+            );
+
+            let mut chunks = Vec::new();
+
+            for section in sections {
+                for offset in 0.. {
+                    let chunk = bridge.export_state(section.clone(), offset, 1);
+
+                    if chunk_len(&chunk) == 0 {
+                        break;
+                    }
+
+                    chunks.push(chunk);
+                }
+            }
+
+            chunks
+        }
+
+        fn state_to_export() -> Vec<Vec<u8>> {
+            let mut bridge = new_bridge_with_cherry(CherryMode::Lock);
+            let (bob, charlie) = (accounts().bob, accounts().charlie);
+
+            assert_eq!(
+                bridge.transfer(
+                    account_hash(charlie),
+                    hash("NATIVE_LEDGER_TOKEN"),
+                    U256::from_u128(300)
+                ),
+                Ok(())
+            );
+            bridge.approve(
+                account_hash(bob),
+                hash("NATIVE_LEDGER_TOKEN"),
+                U256::from_u128(50),
+            );
+            assert_eq!(
+                bridge.bridge_cherry(U256::from_u128(100), U256::from_u128(2), hash("0xdead")),
+                Ok(())
+            );
+            assert_eq!(
+                bridge.receive_cherry(
+                    U256::from_u128(40),
+                    account_hash(bob),
+                    U256::from_u128(2),
+                    7
+                ),
+                Ok(())
+            );
+
+            export_all(&bridge)
+        }
+
+        fn fresh_bridge() -> Bridge {
+            ink_env::test::set_callee::<DefaultEnvironment>([0x09; 32].into());
+            set_caller(accounts().alice);
+
+            Bridge::new_with_allocations(Vec::new(), hash("CHER"))
+        }
+
+        #[ink::test]
+        fn import_state_restores_exported_state() {
+            let chunks = state_to_export();
+            let checksum = Bridge::state_checksum(&chunks);
+            let mut bridge = fresh_bridge();
+            let (first, rest) = chunks.split_at(4);
+
+            assert_eq!(chunks.len(), 11);
+            assert_eq!(
+                bridge.import_state(first.to_vec()),
+                Err(BridgeContractError::ImportNotAllowed)
+            );
+            assert_eq!(bridge.begin_import(checksum), Ok(()));
+            assert_eq!(bridge.import_state(first.to_vec()), Ok(()));
+            assert_eq!(bridge.import_state(rest.to_vec()), Ok(()));
+            assert_eq!(bridge.finalize_import(), Ok(()));
+            assert_eq!(export_all(&bridge), chunks);
+
+            let cher = bridge.audit_token(hash("CHER"), 0, 10);
+
+            assert!(cher.consistent);
+            assert_eq!(cher.total_supply.to_u128(), Some(200));
+            assert_eq!(bridge.locked_balance(hash("CHER")).to_u128(), Some(55));
+            assert_eq!(bridge.collected_fees(hash("CHER")).to_u128(), Some(5));
+            assert_eq!(
+                bridge.outbound_message(0).map(|message| message.status),
+                Some(OutboundStatus::Pending)
+            );
+            assert_eq!(
+                bridge.allowance(
+                    account_hash(accounts().alice),
+                    account_hash(accounts().bob),
+                    hash("NATIVE_LEDGER_TOKEN")
+                ),
+                U256::from_u128(50)
+            );
+            assert_eq!(
+                bridge.begin_import(checksum),
+                Err(BridgeContractError::ImportNotAllowed)
+            );
+            assert_eq!(
+                bridge.import_state(chunks),
+                Err(BridgeContractError::ImportNotAllowed)
+            );
+            assert_eq!(
+                bridge.finalize_import(),
+                Err(BridgeContractError::ImportNotAllowed)
+            );
+        }
+
+        #[ink::test]
+        fn import_state_restores_psp22_and_locked_native_tokens() {
+            let (mut bridge, token) = new_bridge_with_psp22();
+            let alice = accounts().alice;
+
+            mock::set_balance(PSP22_CONTRACT.into(), alice, 100);
+            mock::approve(PSP22_CONTRACT.into(), alice, CONTRACT.into(), 100);
+            ink_env::test::set_account_balance::<DefaultEnvironment>(CONTRACT.into(), 10_000_000);
+            ink_env::test::set_value_transferred::<DefaultEnvironment>(5_000_000);
+
+            assert!(bridge
+                .bridge_out(token.clone(), U256::from_u128(60), U256::from_u128(2))
+                .is_ok());
+            assert!(bridge
+                .bridge_out_native(U256::from_u128(2), hash("0xRecipientOnTargetChain"))
+                .is_ok());
+
+            let chunks = export_all(&bridge);
+            let mut bridge = fresh_bridge();
+
+            assert_eq!(bridge.begin_import(Bridge::state_checksum(&chunks)), Ok(()));
+
+            for chunk in chunks.iter() {
+                assert_eq!(bridge.import_state(vec![chunk.clone()]), Ok(()));
+            }
+
+            assert_eq!(bridge.finalize_import(), Ok(()));
+            assert_eq!(export_all(&bridge), chunks);
+            assert_eq!(
+                bridge.psp22_contract_of(token.clone()),
+                Some(PSP22_CONTRACT.into())
+            );
+            assert_eq!(bridge.locked_balance(token).to_u128(), Some(60));
+            assert_eq!(native_locked(&bridge), Some(5_000_000));
+        }
+
+        #[ink::test]
+        fn import_state_verifies_the_chunks() {
+            let mut chunks = state_to_export();
+            let checksum = Bridge::state_checksum(&chunks);
+            let mut bridge = fresh_bridge();
+
+            set_caller(accounts().bob);
+            assert_eq!(
+                bridge.begin_import(checksum),
+                Err(BridgeContractError::Unauthorized)
+            );
+
+            set_caller(accounts().alice);
+            assert_eq!(bridge.begin_import(checksum), Ok(()));
+            assert_eq!(
+                bridge.begin_import(Bridge::state_checksum(&[])),
+                Err(BridgeContractError::ImportNotAllowed)
+            );
+
+            set_caller(accounts().bob);
+            assert_eq!(
+                bridge.import_state(chunks.clone()),
+                Err(BridgeContractError::Unauthorized)
+            );
+            assert_eq!(
+                bridge.finalize_import(),
+                Err(BridgeContractError::Unauthorized)
+            );
+
+            set_caller(accounts().alice);
+            assert_eq!(
+                bridge.import_state(vec![chunks[0].clone(), vec![0xff]]),
+                Err(BridgeContractError::InvalidStateChunk)
+            );
+            assert_eq!(bridge.token_count(), 0);
+
+            chunks.swap(0, 1);
+            assert_eq!(bridge.import_state(chunks), Ok(()));
+            assert_eq!(
+                bridge.finalize_import(),
+                Err(BridgeContractError::ChecksumMismatch)
+            );
+        }
+
+        #[ink::test]
+        fn import_in_progress_rejects_state_changes() {
+            let chunks = state_to_export();
+            let mut bridge = fresh_bridge();
+            let (first, rest) = chunks.split_at(4);
+            let charlie = account_hash(accounts().charlie);
+
+            assert_eq!(bridge.begin_import(Bridge::state_checksum(&chunks)), Ok(()));
+            assert_eq!(bridge.import_state(first.to_vec()), Ok(()));
+            assert_eq!(
+                bridge.finalize_import(),
+                Err(BridgeContractError::ChecksumMismatch)
+            );
+            assert_eq!(
+                bridge.transfer(
+                    charlie.clone(),
+                    hash("NATIVE_LEDGER_TOKEN"),
+                    U256::from_u128(1)
+                ),
+                Err(BridgeContractError::ImportInProgress)
+            );
+            assert_eq!(
+                bridge.bridge_out(
+                    hash("NATIVE_LEDGER_TOKEN"),
+                    U256::from_u128(1),
+                    U256::from_u128(2)
+                ),
+                Err(BridgeContractError::ImportInProgress)
+            );
+            assert_eq!(
+                bridge.withdraw_fees(hash("CHER"), charlie.clone(), U256::from_u128(1)),
+                Err(BridgeContractError::ImportInProgress)
+            );
+            assert_eq!(
+                bridge.mark_failed(0),
+                Err(BridgeContractError::ImportInProgress)
+            );
+
+            assert_eq!(bridge.import_state(rest.to_vec()), Ok(()));
+            assert_eq!(bridge.finalize_import(), Ok(()));
+            assert_eq!(
+                bridge.transfer(charlie, hash("NATIVE_LEDGER_TOKEN"), U256::from_u128(1)),
+                Ok(())
+            );
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "state import in progress")]
+        fn import_in_progress_rejects_approvals() {
+            let mut bridge = fresh_bridge();
+
+            assert_eq!(bridge.begin_import([0; 32]), Ok(()));
+
+            bridge.approve(
+                account_hash(accounts().bob),
+                hash("CHER"),
+                U256::from_u128(1),
+            );
+        }
+
+        #[ink::test]
+        fn import_state_requires_an_empty_ledger() {
+            let mut bridge = new_bridge();
+
+            assert_eq!(
+                bridge.begin_import(Bridge::state_checksum(&[])),
+                Err(BridgeContractError::ImportNotAllowed)
+            );
+        }
+
+        #[ink::test]
+        fn string_messages_reject_malformed_numbers() {
+            let mut bridge = new_bridge();
+            let bridge_in = |bridge: &mut Bridge, amount: &str| {
+                let nonce = *bridge.processed_inbound_count;
+
+                bridge.bridge_in_string(
+                    "NATIVE_LEDGER_TOKEN".to_string(),
+                    amount.to_string(),
+                    "BOB_ON_TARGET".to_string(),
+                    "2".to_string(),
+                    u64::from(nonce),
+                )
+            };
+
+            for amount in ["", "0x", "12a", "-1", "0xzz", "1 000"] {
+                assert_eq!(
+                    bridge_in(&mut bridge, amount),
+                    Err(BridgeContractError::InvalidNumber),
+                    "{:?}",
+                    amount
+                );
+            }
+
+            assert_eq!(
+                bridge_in(&mut bridge, &"9".repeat(78)),
+                Err(BridgeContractError::InvalidNumber)
+            );
+            assert_eq!(
+                bridge_in(&mut bridge, &format!("0x1{}", "0".repeat(64))),
+                Err(BridgeContractError::InvalidNumber)
+            );
+            assert_eq!(
+                bridge.bridge_out_string(
+                    "NATIVE_LEDGER_TOKEN".to_string(),
+                    "10".to_string(),
+                    "two".to_string()
+                ),
+                Err(BridgeContractError::InvalidNumber)
+            );
+
+            assert_eq!(bridge_in(&mut bridge, "0x0a"), Ok(()));
+            assert_eq!(bridge_in(&mut bridge, &U256::from_u128(5).to_hex()), Ok(()));
+            assert_eq!(
+                bridge
+                    .balance_of(hash("BOB_ON_TARGET"), hash("NATIVE_LEDGER_TOKEN"))
+                    .to_u128(),
+                Some(15)
+            );
+        }
     }
 }
@@ -0,0 +1,231 @@
+//! Two-chain simulation harness for the `Bridge` contract.
+//!
+//! Each [`Chain`] is a `Bridge` instance living at its own address in the ink!
+//! off-chain environment, which keeps contract storage per callee. A
+//! [`Relayer`] reads the `BridgedOut` events a chain emitted and delivers them
+//! to the destination chain, the way an off-chain relayer would against real
+//! nodes. Everything must run inside `ink_env::test::run_test`.
+
+use std::collections::BTreeSet;
+
+use bridge_cherry_contract::bridge_cherry_contract::{
+    Bridge, BridgeContractError, BridgeRoute, OutboundStatus,
+};
+use bridge_cherry_contract::helpers::utils::{MultiChainAddrHash, U256};
+use bridge_event_decoder::{decode_event, BridgeEvent, BridgedOut};
+use ink_env::{test, AccountId, DefaultEnvironment};
+
+pub type BridgeResult<T> = Result<T, BridgeContractError>;
+
+pub fn account_hash(account: AccountId) -> MultiChainAddrHash {
+    (account.as_ref() as &[u8]).into()
+}
+
+pub fn hash(s: &str) -> MultiChainAddrHash {
+    MultiChainAddrHash::from(s.to_string())
+}
+
+pub fn amount(value: u128) -> U256 {
+    U256::from_u128(value)
+}
+
+/// A `Bridge` deployment on one simulated chain.
+pub struct Chain {
+    pub chain_id: U256,
+    pub address: AccountId,
+    bridge: Bridge,
+    outbox: Vec<BridgedOut>,
+}
+
+impl Chain {
+    /// Deploys a bridge at `address`, owned by `owner`, with `owner` holding
+    /// the initial supply of `initial_token`.
+    pub fn deploy(
+        chain_id: u128,
+        address: AccountId,
+        owner: AccountId,
+        initial_token: &str,
+        initial_supply: u128,
+    ) -> Self {
+        test::set_callee::<DefaultEnvironment>(address);
+        test::set_caller::<DefaultEnvironment>(owner);
+
+        let before = test::recorded_events().count();
+        let bridge = Bridge::new(hash(initial_token), amount(initial_supply), hash("CHER"));
+        let mut chain = Chain {
+            chain_id: amount(chain_id),
+            address,
+            bridge,
+            outbox: Vec::new(),
+        };
+
+        chain.collect_events(before);
+
+        chain
+    }
+
+    /// Calls a message as `caller`. Events of failed calls are dropped, as
+    /// the chain would revert them.
+    pub fn call<T>(
+        &mut self,
+        caller: AccountId,
+        message: impl FnOnce(&mut Bridge) -> BridgeResult<T>,
+    ) -> BridgeResult<T> {
+        test::set_callee::<DefaultEnvironment>(self.address);
+        test::set_caller::<DefaultEnvironment>(caller);
+
+        let before = test::recorded_events().count();
+        let result = message(&mut self.bridge);
+
+        if result.is_ok() {
+            self.collect_events(before);
+        }
+
+        result
+    }
+
+    pub fn query<T>(&self, query: impl FnOnce(&Bridge) -> T) -> T {
+        test::set_callee::<DefaultEnvironment>(self.address);
+
+        query(&self.bridge)
+    }
+
+    pub fn balance_of(&self, owner: AccountId, token: &str) -> Option<u128> {
+        self.query(|bridge| {
+            bridge
+                .balance_of(account_hash(owner), hash(token))
+                .to_u128()
+        })
+    }
+
+    pub fn outbound_status(&self, nonce: u64) -> Option<OutboundStatus> {
+        self.query(|bridge| bridge.outbound_message(nonce).map(|m| m.status))
+    }
+
+    /// Every `BridgedOut` this chain has emitted so far.
+    pub fn outbox(&self) -> &[BridgedOut] {
+        &self.outbox
+    }
+
+    fn collect_events(&mut self, before: usize) {
+        for event in test::recorded_events().skip(before) {
+            match decode_event(&event.data) {
+                Ok(BridgeEvent::BridgedOut(out)) => self.outbox.push(out),
+                Ok(_) => {}
+                Err(e) => panic!("undecodable contract event: {}", e),
+            }
+        }
+    }
+}
+
+/// What happened to a relayed message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Delivery {
+    /// Delivered to the destination and confirmed on the source.
+    Delivered(u64),
+    /// The destination is paused; the message stays pending for a retry.
+    Deferred(u64),
+    /// The destination rejected the message; it was marked failed on the
+    /// source so the sender can refund it.
+    Failed(u64, BridgeContractError),
+    /// The message passed its `expires_at` before it was delivered; it was
+    /// marked failed on the source without being submitted.
+    Expired(u64),
+}
+
+/// Relays messages between chains as `account`, which must be a relayer and
+/// Cherry attester on both of them.
+pub struct Relayer {
+    pub account: AccountId,
+    delivered: BTreeSet<([u8; 32], u64)>,
+}
+
+impl Relayer {
+    pub fn new(account: AccountId) -> Self {
+        Relayer {
+            account,
+            delivered: BTreeSet::new(),
+        }
+    }
+
+    /// Delivers every message `source` addressed to `destination` that is
+    /// still pending on `source`, and fails those past their expiry.
+    pub fn relay(&mut self, source: &mut Chain, destination: &mut Chain) -> Vec<Delivery> {
+        let pending: Vec<BridgedOut> = source
+            .outbox()
+            .iter()
+            .filter(|out| out.target_chain == destination.chain_id)
+            .filter(|out| {
+                !self
+                    .delivered
+                    .contains(&(*source.chain_id.get(), out.nonce))
+            })
+            .filter(|out| source.outbound_status(out.nonce) == Some(OutboundStatus::Pending))
+            .cloned()
+            .collect();
+
+        pending
+            .iter()
+            .map(|out| {
+                let nonce = out.nonce;
+
+                if ink_env::block_timestamp::<DefaultEnvironment>() > out.expires_at {
+                    source
+                        .call(self.account, |bridge| bridge.mark_failed(nonce))
+                        .expect("relayer can fail pending messages");
+
+                    return Delivery::Expired(nonce);
+                }
+
+                match self.deliver(source.chain_id, destination, out) {
+                    Ok(()) => {
+                        self.delivered.insert((*source.chain_id.get(), nonce));
+                        source
+                            .call(self.account, |bridge| bridge.confirm_delivered(nonce))
+                            .expect("relayer can confirm pending messages");
+
+                        Delivery::Delivered(nonce)
+                    }
+                    Err(BridgeContractError::Paused) => Delivery::Deferred(nonce),
+                    Err(e) => {
+                        source
+                            .call(self.account, |bridge| bridge.mark_failed(nonce))
+                            .expect("relayer can fail pending messages");
+
+                        Delivery::Failed(nonce, e)
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Submits `out` to `destination` without consulting any bookkeeping,
+    /// which is how a replaying relayer would behave.
+    pub fn deliver(
+        &self,
+        source_chain: U256,
+        destination: &mut Chain,
+        out: &BridgedOut,
+    ) -> BridgeResult<()> {
+        let recipient = out
+            .recipient
+            .clone()
+            .unwrap_or_else(|| out.sender_address.clone());
+
+        destination.call(self.account, |bridge| match out.route {
+            BridgeRoute::Token => bridge.bridge_in(
+                out.token_address.clone(),
+                out.amount,
+                recipient,
+                source_chain,
+                out.nonce,
+            ),
+            BridgeRoute::Native => {
+                bridge.bridge_in_native(out.amount, recipient, source_chain, out.nonce)
+            }
+            BridgeRoute::CherryLock | BridgeRoute::CherryBurn => {
+                bridge.receive_cherry(out.amount, recipient, source_chain, out.nonce)
+            }
+        })
+    }
+}
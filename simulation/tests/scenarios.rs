@@ -0,0 +1,373 @@
+use bridge_cherry_contract::bridge_cherry_contract::{
+    BridgeContractError, ChainConfig, CherryMode, OutboundStatus, TokenAllocation, TokenMetadata,
+};
+use bridge_simulation::{account_hash, amount, hash, Chain, Delivery, Relayer};
+use ink_env::test::{default_accounts, run_test, DefaultAccounts};
+use ink_env::DefaultEnvironment;
+
+const CHAIN_A: [u8; 32] = [0xa1; 32];
+const CHAIN_B: [u8; 32] = [0xb2; 32];
+
+struct Network {
+    a: Chain,
+    b: Chain,
+    relayer: Relayer,
+    accounts: DefaultAccounts<DefaultEnvironment>,
+}
+
+fn allocation(token: &str, supply: u128, recipient: ink_env::AccountId) -> TokenAllocation {
+    TokenAllocation {
+        token: hash(token),
+        supply: amount(supply),
+        recipient: account_hash(recipient),
+        metadata: TokenMetadata {
+            name: token.to_string(),
+            symbol: token.to_string(),
+            decimals: 12,
+        },
+    }
+}
+
+/// Chain A (id 1) is Cherry's home and locks it; chain B (id 2) burns and
+/// mints it. Django owns both bridges, Eve relays, Alice holds USD and CHER
+/// on A, and Eve keeps a USD float on B to pay inbound transfers.
+fn network(accounts: DefaultAccounts<DefaultEnvironment>) -> Network {
+    let owner = accounts.django;
+    let relayer = accounts.eve;
+    let mut a = Chain::deploy(1, CHAIN_A.into(), owner, "USD", 1_000);
+    let mut b = Chain::deploy(2, CHAIN_B.into(), owner, "USD", 1_000);
+
+    for chain in [&mut a, &mut b] {
+        chain
+            .call(owner, |bridge| bridge.set_relayer(relayer, true))
+            .unwrap();
+        chain
+            .call(owner, |bridge| bridge.set_cherry_attester(relayer, true))
+            .unwrap();
+    }
+
+    a.call(owner, |bridge| {
+        bridge.set_chain(
+            amount(2),
+            Some(ChainConfig {
+                fee_bps: 500,
+                cherry_fee_bps: 500,
+                cherry_mode: CherryMode::Lock,
+            }),
+        )
+    })
+    .unwrap();
+    b.call(owner, |bridge| {
+        bridge.set_chain(
+            amount(1),
+            Some(ChainConfig {
+                fee_bps: 0,
+                cherry_fee_bps: 0,
+                cherry_mode: CherryMode::Burn,
+            }),
+        )
+    })
+    .unwrap();
+
+    a.call(owner, |bridge| {
+        bridge.create_token(allocation("CHER", 200, accounts.alice))
+    })
+    .unwrap();
+    a.call(owner, |bridge| {
+        bridge.transfer(account_hash(accounts.alice), hash("USD"), amount(100))
+    })
+    .unwrap();
+    b.call(owner, |bridge| {
+        bridge.transfer(account_hash(relayer), hash("USD"), amount(100))
+    })
+    .unwrap();
+
+    approve_custody(&mut a, accounts.alice, "USD");
+    approve_custody(&mut a, accounts.alice, "CHER");
+
+    Network {
+        a,
+        b,
+        relayer: Relayer::new(relayer),
+        accounts,
+    }
+}
+
+fn approve_custody(chain: &mut Chain, owner: ink_env::AccountId, token: &str) {
+    let custody = account_hash(chain.address);
+
+    chain
+        .call(owner, |bridge| {
+            bridge.approve(custody, hash(token), amount(200));
+
+            Ok(())
+        })
+        .unwrap();
+}
+
+#[test]
+fn token_round_trip_returns_to_sender() {
+    run_test::<DefaultEnvironment, _>(|_| {
+        let Network {
+            mut a,
+            mut b,
+            mut relayer,
+            accounts,
+        } = network(default_accounts::<DefaultEnvironment>());
+        let alice = accounts.alice;
+
+        a.call(alice, |bridge| {
+            bridge.bridge_out(hash("USD"), amount(60), amount(2))
+        })
+        .unwrap();
+
+        assert_eq!(relayer.relay(&mut a, &mut b), vec![Delivery::Delivered(0)]);
+        assert_eq!(a.balance_of(alice, "USD"), Some(40));
+        assert_eq!(b.balance_of(alice, "USD"), Some(57));
+        assert_eq!(b.balance_of(accounts.eve, "USD"), Some(43));
+        assert_eq!(a.outbound_status(0), Some(OutboundStatus::Delivered));
+
+        approve_custody(&mut b, alice, "USD");
+        b.call(alice, |bridge| {
+            bridge.bridge_out(hash("USD"), amount(57), amount(1))
+        })
+        .unwrap();
+
+        assert_eq!(relayer.relay(&mut b, &mut a), vec![Delivery::Delivered(0)]);
+        assert_eq!(a.balance_of(alice, "USD"), Some(97));
+        assert_eq!(b.balance_of(alice, "USD"), Some(0));
+        assert_eq!(
+            a.query(|bridge| bridge.locked_balance(hash("USD")).to_u128()),
+            Some(0)
+        );
+        assert_eq!(
+            a.query(|bridge| bridge.collected_fees(hash("USD")).to_u128()),
+            Some(3)
+        );
+
+        Ok(())
+    })
+    .unwrap();
+}
+
+#[test]
+fn cherry_round_trip_mints_and_burns_on_the_remote_chain() {
+    run_test::<DefaultEnvironment, _>(|_| {
+        let Network {
+            mut a,
+            mut b,
+            mut relayer,
+            accounts,
+        } = network(default_accounts::<DefaultEnvironment>());
+        let (alice, bob) = (accounts.alice, accounts.bob);
+
+        a.call(alice, |bridge| {
+            bridge.bridge_cherry(amount(100), amount(2), account_hash(bob))
+        })
+        .unwrap();
+
+        assert_eq!(relayer.relay(&mut a, &mut b), vec![Delivery::Delivered(0)]);
+        assert_eq!(b.balance_of(bob, "CHER"), Some(95));
+        assert_eq!(
+            b.query(|bridge| bridge.total_supply(hash("CHER")).to_u128()),
+            Some(95)
+        );
+
+        // Chain B burns without a fee, so bob never approves custody.
+        b.call(bob, |bridge| {
+            bridge.bridge_cherry(amount(95), amount(1), account_hash(alice))
+        })
+        .unwrap();
+
+        assert_eq!(relayer.relay(&mut b, &mut a), vec![Delivery::Delivered(0)]);
+        assert_eq!(a.balance_of(alice, "CHER"), Some(195));
+        assert_eq!(
+            b.query(|bridge| bridge.total_supply(hash("CHER")).to_u128()),
+            Some(0)
+        );
+        assert_eq!(
+            a.query(|bridge| bridge.locked_balance(hash("CHER")).to_u128()),
+            Some(0)
+        );
+
+        Ok(())
+    })
+    .unwrap();
+}
+
+#[test]
+fn replayed_messages_are_not_delivered_twice() {
+    run_test::<DefaultEnvironment, _>(|_| {
+        let Network {
+            mut a,
+            mut b,
+            mut relayer,
+            accounts,
+        } = network(default_accounts::<DefaultEnvironment>());
+        let bob = accounts.bob;
+
+        a.call(accounts.alice, |bridge| {
+            bridge.bridge_cherry(amount(100), amount(2), account_hash(bob))
+        })
+        .unwrap();
+
+        assert_eq!(relayer.relay(&mut a, &mut b), vec![Delivery::Delivered(0)]);
+        assert_eq!(relayer.relay(&mut a, &mut b), vec![]);
+
+        let replayed = a.outbox()[0].clone();
+
+        assert_eq!(
+            relayer.deliver(a.chain_id, &mut b, &replayed),
+            Err(BridgeContractError::AlreadyProcessed)
+        );
+
+        let mut restarted = Relayer::new(accounts.eve);
+
+        assert_eq!(restarted.relay(&mut a, &mut b), vec![]);
+        assert_eq!(b.balance_of(bob, "CHER"), Some(95));
+
+        Ok(())
+    })
+    .unwrap();
+}
+
+#[test]
+fn replayed_token_messages_are_not_delivered_twice() {
+    run_test::<DefaultEnvironment, _>(|_| {
+        let Network {
+            mut a,
+            mut b,
+            mut relayer,
+            accounts,
+        } = network(default_accounts::<DefaultEnvironment>());
+        let alice = accounts.alice;
+
+        a.call(alice, |bridge| {
+            bridge.bridge_out(hash("USD"), amount(60), amount(2))
+        })
+        .unwrap();
+
+        assert_eq!(relayer.relay(&mut a, &mut b), vec![Delivery::Delivered(0)]);
+
+        let replayed = a.outbox()[0].clone();
+
+        assert_eq!(
+            relayer.deliver(a.chain_id, &mut b, &replayed),
+            Err(BridgeContractError::AlreadyProcessed)
+        );
+        assert_eq!(b.balance_of(alice, "USD"), Some(57));
+        assert_eq!(b.balance_of(accounts.eve, "USD"), Some(43));
+
+        Ok(())
+    })
+    .unwrap();
+}
+
+#[test]
+fn paused_destination_defers_delivery() {
+    run_test::<DefaultEnvironment, _>(|_| {
+        let Network {
+            mut a,
+            mut b,
+            mut relayer,
+            accounts,
+        } = network(default_accounts::<DefaultEnvironment>());
+        let bob = accounts.bob;
+
+        b.call(accounts.django, |bridge| bridge.set_paused(true))
+            .unwrap();
+        a.call(accounts.alice, |bridge| {
+            bridge.bridge_cherry(amount(100), amount(2), account_hash(bob))
+        })
+        .unwrap();
+
+        assert_eq!(relayer.relay(&mut a, &mut b), vec![Delivery::Deferred(0)]);
+        assert_eq!(a.outbound_status(0), Some(OutboundStatus::Pending));
+        assert_eq!(b.balance_of(bob, "CHER"), Some(0));
+
+        b.call(accounts.django, |bridge| bridge.set_paused(false))
+            .unwrap();
+
+        assert_eq!(relayer.relay(&mut a, &mut b), vec![Delivery::Delivered(0)]);
+        assert_eq!(b.balance_of(bob, "CHER"), Some(95));
+
+        Ok(())
+    })
+    .unwrap();
+}
+
+#[test]
+fn undeliverable_message_is_refunded_on_the_source() {
+    run_test::<DefaultEnvironment, _>(|_| {
+        let Network {
+            mut a,
+            mut b,
+            mut relayer,
+            accounts,
+        } = network(default_accounts::<DefaultEnvironment>());
+        let alice = accounts.alice;
+
+        a.call(accounts.django, |bridge| {
+            bridge.create_token(allocation("GOLD", 100, alice))
+        })
+        .unwrap();
+        approve_custody(&mut a, alice, "GOLD");
+        a.call(alice, |bridge| {
+            bridge.bridge_out(hash("GOLD"), amount(60), amount(2))
+        })
+        .unwrap();
+
+        assert_eq!(
+            relayer.relay(&mut a, &mut b),
+            vec![Delivery::Failed(0, BridgeContractError::UnknownToken)]
+        );
+        assert_eq!(a.outbound_status(0), Some(OutboundStatus::Failed));
+
+        a.call(alice, |bridge| bridge.refund(0)).unwrap();
+
+        assert_eq!(a.balance_of(alice, "GOLD"), Some(97));
+        assert_eq!(a.outbound_status(0), Some(OutboundStatus::Refunded));
+        assert_eq!(relayer.relay(&mut a, &mut b), vec![]);
+
+        Ok(())
+    })
+    .unwrap();
+}
+
+#[test]
+fn expired_message_is_failed_instead_of_delivered() {
+    run_test::<DefaultEnvironment, _>(|_| {
+        let Network {
+            mut a,
+            mut b,
+            mut relayer,
+            accounts,
+        } = network(default_accounts::<DefaultEnvironment>());
+        let alice = accounts.alice;
+
+        a.call(accounts.django, |bridge| bridge.set_outbound_ttl(10))
+            .unwrap();
+        a.call(alice, |bridge| {
+            bridge.bridge_out(hash("USD"), amount(60), amount(2))
+        })
+        .unwrap();
+
+        ink_env::test::advance_block::<DefaultEnvironment>();
+        ink_env::test::advance_block::<DefaultEnvironment>();
+
+        assert_eq!(
+            a.call(alice, |bridge| bridge.refund(0)),
+            Err(BridgeContractError::NotRefundable)
+        );
+        assert_eq!(relayer.relay(&mut a, &mut b), vec![Delivery::Expired(0)]);
+        assert_eq!(b.balance_of(alice, "USD"), Some(0));
+
+        a.call(alice, |bridge| bridge.refund(0)).unwrap();
+
+        assert_eq!(a.balance_of(alice, "USD"), Some(97));
+        assert_eq!(a.outbound_status(0), Some(OutboundStatus::Refunded));
+
+        Ok(())
+    })
+    .unwrap();
+}
@@ -0,0 +1,122 @@
+//! Property tests checking `U256` against `num-bigint`.
+
+use bridge_cherry_contract::helpers::utils::U256;
+use num_bigint::BigUint;
+use proptest::prelude::*;
+
+fn u256(bytes: [u8; 32]) -> U256 {
+    U256::from_hex(hex::encode(bytes).as_bytes()).unwrap()
+}
+
+fn big(value: &U256) -> BigUint {
+    BigUint::from_bytes_be(value.get())
+}
+
+fn modulus() -> BigUint {
+    BigUint::from(1u8) << 256usize
+}
+
+/// Uniform bytes rarely exercise long carry chains or small values, so mix in
+/// values around zero, `u128::MAX` and `2^256 - 1`.
+fn any_u256() -> impl Strategy<Value = U256> {
+    prop_oneof![
+        any::<[u8; 32]>().prop_map(u256),
+        any::<u128>().prop_map(U256::from_u128),
+        (0u8..4).prop_map(|n| U256::from_u128(n as u128)),
+        (0u8..4).prop_map(|n| u256([0xff - n; 32])),
+        (0usize..32).prop_map(|zeros| {
+            let mut bytes = [0xffu8; 32];
+
+            bytes[..zeros].fill(0);
+
+            u256(bytes)
+        }),
+    ]
+}
+
+proptest! {
+    #[test]
+    fn add_wraps_like_bignum(a in any_u256(), b in any_u256()) {
+        let sum = U256::add_a_with_b(&a, &b);
+
+        prop_assert_eq!(big(&sum), (big(&a) + big(&b)) % modulus());
+    }
+
+    #[test]
+    fn subtract_wraps_like_bignum(a in any_u256(), b in any_u256()) {
+        let difference = U256::subtract_b_from_a(&a, &b);
+
+        prop_assert_eq!(big(&difference), (big(&a) + modulus() - big(&b)) % modulus());
+    }
+
+    #[test]
+    fn add_then_subtract_is_identity(a in any_u256(), b in any_u256()) {
+        let sum = U256::add_a_with_b(&a, &b);
+
+        prop_assert_eq!(U256::subtract_b_from_a(&sum, &b), a);
+    }
+
+    #[test]
+    fn greater_than_matches_bignum(a in any_u256(), b in any_u256()) {
+        prop_assert_eq!(U256::a_greater_than_b(&a, &b), big(&a) > big(&b));
+        prop_assert!(!U256::a_greater_than_b(&a, &a));
+    }
+
+    #[test]
+    fn decimal_round_trips(a in any_u256()) {
+        let decimal = a.to_decimal();
+
+        prop_assert_eq!(&decimal, &big(&a).to_string());
+        prop_assert_eq!(U256::from_decimal(&decimal), Some(a));
+        prop_assert_eq!(U256::parse(&decimal), Some(a));
+    }
+
+    #[test]
+    fn hex_round_trips(a in any_u256()) {
+        let hex = a.to_hex();
+
+        prop_assert_eq!(&hex, &format!("{:064x}", big(&a)));
+        prop_assert_eq!(U256::from_hex(hex.as_bytes()), Some(a));
+        prop_assert_eq!(U256::parse(&format!("0x{:x}", big(&a))), Some(a));
+    }
+
+    #[test]
+    fn parse_rejects_values_above_256_bits(a in any_u256(), extra in 1u32..64) {
+        let too_big = big(&a) + (BigUint::from(extra) << 256usize);
+
+        prop_assert_eq!(U256::parse(&too_big.to_string()), None);
+        prop_assert_eq!(U256::parse(&format!("0x{:x}", too_big)), None);
+    }
+
+    #[test]
+    fn u128_round_trips(v in any::<u128>()) {
+        let a = U256::from_u128(v);
+
+        prop_assert_eq!(big(&a), BigUint::from(v));
+        prop_assert_eq!(a.to_u128(), Some(v));
+    }
+
+    #[test]
+    fn to_u128_rejects_wide_values(a in any_u256()) {
+        let expected = u128::try_from(big(&a)).ok();
+
+        prop_assert_eq!(a.to_u128(), expected);
+    }
+
+    #[test]
+    fn apply_bps_rounds_down(v in any_u256(), bps in any::<u32>()) {
+        let expected = big(&v) * bps / 10_000u32;
+        let result = v.apply_bps(bps);
+
+        if expected < modulus() {
+            prop_assert_eq!(result.as_ref().map(big), Some(expected));
+        } else {
+            prop_assert!(result.is_none());
+        }
+    }
+
+    #[test]
+    fn apply_bps_never_fails_up_to_whole_amount(v in any_u256(), bps in 0u32..=10_000) {
+        prop_assert!(v.apply_bps(bps).is_some());
+    }
+}